@@ -3,6 +3,7 @@ fn main() {
         "proto/euclidolap.proto",
         "proto/olapmeta.proto",
         "proto/agg-service.proto",
+        "proto/metadata_snapshot.proto",
     ];
 
     let proto_includes = ["proto"];
@@ -22,5 +23,8 @@ fn main() {
         println!("cargo:rerun-if-changed={}", proto);
     }
 
+    // Rerun build script if the MDX grammar changes
+    println!("cargo:rerun-if-changed=src/mdx_grammar.lalrpop");
+
     lalrpop::process_src().unwrap();
 }
@@ -1,28 +1,52 @@
 use core::panic;
 
 use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use agg_service::agg_service_client::AggServiceClient;
-use agg_service::{GrpcAggregationRequest, GrpcVectorCoordinate};
-use tonic::transport::Channel;
+use agg_service::{GrpcAggregationRequest, GrpcImportMeasureRequest, GrpcVectorCoordinate};
+use once_cell::sync::OnceCell;
+use tokio::time::{sleep, timeout, Duration};
+use tonic::transport::{Channel, Endpoint};
+use tonic::Code;
 
+use crate::cfg::{get_cfg, DEFAULT_AGG_GRPC_URL, DEFAULT_AGG_MAX_RETRIES, DEFAULT_AGG_TIMEOUT_MS};
 use crate::exmdx::mdd::TupleVector;
 use crate::mdd::MemberRole;
 use crate::mdd::MultiDimensionalContext;
+use crate::result_cache::{self, CELL_CACHE};
 
 pub mod agg_service {
     tonic::include_proto!("agg_service");
 }
 
+// 进程内共享的聚合服务连接，第一次请求时才建立连接，断开后由 tonic 自动重连
+static AGG_CHANNEL: OnceCell<Channel> = OnceCell::new();
+
+const RETRY_BASE_DELAY_MS: u64 = 100;
+
+#[derive(Clone)]
 pub struct AggServiceGrpcClient {
     client: AggServiceClient<Channel>,
 }
 
 impl AggServiceGrpcClient {
-    // Create a new gRPC client instance
-    pub async fn new(addr: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let client = AggServiceClient::connect(addr.to_string()).await?;
-        Ok(Self { client })
+    /// 返回使用共享连接的客户端，聚合服务地址来自配置 agg_grpc_url。
+    pub fn get_cli() -> Self {
+        let channel = AGG_CHANNEL.get_or_init(|| {
+            let addr = get_cfg()
+                .agg_grpc_url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_AGG_GRPC_URL.to_string());
+            Endpoint::from_shared(addr.clone())
+                .unwrap_or_else(|e| panic!("[agg-001] Invalid agg_grpc_url {}: {}", addr, e))
+                .connect_timeout(Duration::from_secs(3))
+                .tcp_keepalive(Some(Duration::from_secs(60)))
+                .connect_lazy()
+        });
+        Self {
+            client: AggServiceClient::new(channel.clone()),
+        }
     }
 
     // Send AggregationRequest and get AggregationResponse
@@ -30,7 +54,7 @@ impl AggServiceGrpcClient {
         &mut self,
         cube_gid: u64,
         coordinates: Vec<GrpcVectorCoordinate>,
-    ) -> Result<(u64, Vec<f64>, Vec<bool>), Box<dyn std::error::Error>> {
+    ) -> Result<(u64, Vec<f64>, Vec<bool>), tonic::Status> {
         let request = tonic::Request::new(GrpcAggregationRequest {
             cube_gid,
             grpc_vector_coordinates: coordinates,
@@ -40,14 +64,145 @@ impl AggServiceGrpcClient {
 
         Ok((response.cube_gid, response.values, response.null_flags))
     }
+
+    /// 带超时的聚合请求，连接失败、超时等暂时性错误按指数退避加随机抖动重试，
+    /// 重试次数用完后返回错误信息。
+    pub async fn aggregates_with_retry(
+        &mut self,
+        cube_gid: u64,
+        coordinates: Vec<GrpcVectorCoordinate>,
+    ) -> Result<(u64, Vec<f64>, Vec<bool>), String> {
+        let config = get_cfg();
+        let call_timeout =
+            Duration::from_millis(config.agg_timeout_ms.unwrap_or(DEFAULT_AGG_TIMEOUT_MS));
+        let max_retries = config.agg_max_retries.unwrap_or(DEFAULT_AGG_MAX_RETRIES);
+
+        let mut attempt: u32 = 0;
+        loop {
+            let status =
+                match timeout(call_timeout, self.aggregates(cube_gid, coordinates.clone())).await {
+                    Ok(Ok(result)) => return Ok(result),
+                    Ok(Err(status)) => status,
+                    Err(_) => tonic::Status::deadline_exceeded(format!(
+                        "no response within {} ms",
+                        call_timeout.as_millis()
+                    )),
+                };
+
+            let retryable = matches!(
+                status.code(),
+                Code::Unavailable
+                    | Code::DeadlineExceeded
+                    | Code::ResourceExhausted
+                    | Code::Aborted
+            );
+            if !retryable || attempt >= max_retries {
+                return Err(format!(
+                    "[agg-002] Aggregation service call failed after {} attempt(s): {}",
+                    attempt + 1,
+                    status
+                ));
+            }
+
+            sleep(retry_delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    /// 导入度量数据，成功后递增数据版本，使缓存的单元格值和查询结果失效。
+    pub async fn import_measure_data(&mut self, file_path: String) -> Result<String, String> {
+        let request = tonic::Request::new(GrpcImportMeasureRequest { file_path });
+        let response = self
+            .client
+            .import_measure_data(request)
+            .await
+            .map_err(|status| format!("[agg-003] Failed to import measure data: {}", status))?
+            .into_inner();
+        if !response.success {
+            return Err(format!(
+                "[agg-003] Failed to import measure data: {}",
+                response.message
+            ));
+        }
+        result_cache::bump_data_version();
+        Ok(response.message)
+    }
 }
 
+/// 先查跨查询的单元格缓存，只把未命中的坐标发给聚合服务，返回值与 aggregates 相同。
+async fn cached_aggregates(
+    cube_gid: u64,
+    gvc_list: Vec<GrpcVectorCoordinate>,
+) -> Result<(u64, Vec<f64>, Vec<bool>), String> {
+    if !CELL_CACHE.lock().unwrap().is_enabled() {
+        return AggServiceGrpcClient::get_cli()
+            .aggregates_with_retry(cube_gid, gvc_list)
+            .await;
+    }
+
+    let version = result_cache::current_version();
+    let keys: Vec<(u64, CellKey)> = gvc_list
+        .iter()
+        .map(|gvc| (cube_gid, (gvc.member_gid_arr.clone(), gvc.measure_index)))
+        .collect();
+
+    let mut values = vec![0.0; gvc_list.len()];
+    let mut null_flags = vec![true; gvc_list.len()];
+    let mut miss_indices: Vec<usize> = Vec::new();
+    {
+        let mut cache = CELL_CACHE.lock().unwrap();
+        for (idx, key) in keys.iter().enumerate() {
+            match cache.get(key, version) {
+                Some((val, null_flag)) => {
+                    values[idx] = val;
+                    null_flags[idx] = null_flag;
+                }
+                None => miss_indices.push(idx),
+            }
+        }
+    }
+
+    if miss_indices.is_empty() {
+        return Ok((cube_gid, values, null_flags));
+    }
+
+    let miss_list: Vec<GrpcVectorCoordinate> = miss_indices
+        .iter()
+        .map(|idx| gvc_list[*idx].clone())
+        .collect();
+    let (_, miss_vals, miss_null_flags) = AggServiceGrpcClient::get_cli()
+        .aggregates_with_retry(cube_gid, miss_list)
+        .await?;
+
+    let mut cache = CELL_CACHE.lock().unwrap();
+    for ((idx, val), null_flag) in miss_indices.into_iter().zip(miss_vals).zip(miss_null_flags) {
+        values[idx] = val;
+        null_flags[idx] = null_flag;
+        let bytes = result_cache::cell_entry_bytes(&keys[idx]);
+        cache.insert(keys[idx].clone(), (val, null_flag), version, bytes);
+    }
+
+    Ok((cube_gid, values, null_flags))
+}
+
+/// 第 n 次重试前的等待时间：RETRY_BASE_DELAY_MS * 2^n，再加上不超过一半的随机抖动，
+/// 避免大量查询同时重试。
+fn retry_delay(attempt: u32) -> Duration {
+    let base_ms = RETRY_BASE_DELAY_MS << attempt.min(6);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    Duration::from_millis(base_ms + nanos % (base_ms / 2 + 1))
+}
+
+/// 返回 (cube gid, 值, Null 标志)；聚合服务调用失败时返回错误信息，由调用方作为错误单元格返回。
 pub async fn basic_aggregates(
     coordinates: Vec<TupleVector>,
     context: &MultiDimensionalContext,
-) -> (u64, Vec<f64>, Vec<bool>) {
+) -> Result<(u64, Vec<f64>, Vec<bool>), String> {
     if coordinates.is_empty() {
-        return (context.cube.gid, vec![], vec![]);
+        return Ok((context.cube.gid, vec![], vec![]));
     }
 
     let coordinates_len = coordinates.len();
@@ -74,23 +229,16 @@ pub async fn basic_aggregates(
 
     if true_indexes.is_empty() {
         // 如果没有有权限的坐标，返回 (context.cube.gid, coordinates 长度的 vec 值都为 0, coordinates 长度的 vec 值都为 true)
-        return (
+        return Ok((
             context.cube.gid,
             vec![0.0; coordinates_len],
             vec![true; coordinates_len],
-        );
+        ));
     }
 
-    let mut grpc_cli = AggServiceGrpcClient::new("http://127.0.0.1:16060")
-        .await
-        .expect("Failed to create client");
-
     let gvc_list: Vec<GrpcVectorCoordinate> = transform_coordinates(true_coordinates);
 
-    let result: (u64, Vec<f64>, Vec<bool>) = grpc_cli
-        .aggregates(context.cube.gid, gvc_list)
-        .await
-        .unwrap();
+    let result: (u64, Vec<f64>, Vec<bool>) = cached_aggregates(context.cube.gid, gvc_list).await?;
 
     /*
         根据 true_indexes 创建一个 map，
@@ -114,48 +262,54 @@ pub async fn basic_aggregates(
         }
     }
 
-    (context.cube.gid, fin_values, fin_null_flags)
+    Ok((context.cube.gid, fin_values, fin_null_flags))
+}
+
+/// 基本单元格在聚合服务中的坐标：按维度角色 gid 排序的成员 gid 列表和度量索引。
+pub type CellKey = (Vec<u64>, u32);
+
+pub fn coordinate_key(coordinate: &TupleVector) -> CellKey {
+    let gvc = transform_coordinate(coordinate.clone());
+    (gvc.member_gid_arr, gvc.measure_index)
 }
 
 fn transform_coordinates(coordinates: Vec<TupleVector>) -> Vec<GrpcVectorCoordinate> {
-    let mut grpc_coordinates: Vec<GrpcVectorCoordinate> = Vec::new();
-
-    for ocv in coordinates {
-        let mut member_roles = ocv.member_roles;
-        let mut measure_index: u32 = 0;
-        member_roles.retain(|mr| match mr {
-            MemberRole::BaseMember { dim_role, member } => {
-                if dim_role.measure_flag {
-                    measure_index = member.measure_index;
-                }
-                !dim_role.measure_flag
-            }
-            MemberRole::FormulaMember { .. } => {
-                panic!("FormulaMember is not supported in grpc_client.");
-            }
-        });
-        member_roles.sort_by_key(|mr| mr.get_dim_role_gid());
-
-        let mut gvc = GrpcVectorCoordinate {
-            member_gid_arr: vec![],
-            measure_index,
-        };
-
-        for mr in member_roles {
-            if let MemberRole::BaseMember {
-                dim_role: _,
-                member,
-            } = mr
-            {
-                gvc.member_gid_arr
-                    .push(if member.level == 0 { 0 } else { member.gid });
-            } else {
-                panic!("FormulaMember is not supported in grpc_client.");
+    coordinates.into_iter().map(transform_coordinate).collect()
+}
+
+fn transform_coordinate(ocv: TupleVector) -> GrpcVectorCoordinate {
+    let mut member_roles = ocv.member_roles;
+    let mut measure_index: u32 = 0;
+    member_roles.retain(|mr| match mr {
+        MemberRole::BaseMember { dim_role, member } => {
+            if dim_role.measure_flag {
+                measure_index = member.measure_index;
             }
+            !dim_role.measure_flag
         }
+        MemberRole::FormulaMember { .. } => {
+            panic!("FormulaMember is not supported in grpc_client.");
+        }
+    });
+    member_roles.sort_by_key(|mr| mr.get_dim_role_gid());
+
+    let mut gvc = GrpcVectorCoordinate {
+        member_gid_arr: vec![],
+        measure_index,
+    };
 
-        grpc_coordinates.push(gvc);
+    for mr in member_roles {
+        if let MemberRole::BaseMember {
+            dim_role: _,
+            member,
+        } = mr
+        {
+            gvc.member_gid_arr
+                .push(if member.level == 0 { 0 } else { member.gid });
+        } else {
+            panic!("FormulaMember is not supported in grpc_client.");
+        }
     }
 
-    grpc_coordinates
+    gvc
 }
@@ -1,30 +1,35 @@
 // calculation module
 
-use crate::exmdx::ast::ToVectorValue;
+use crate::exmdx::ast::{AstExpression, ToVectorValue};
 
 use crate::exmdx::mdd::TupleVector;
 use crate::mdd::MemberRole;
-use crate::mdd::{VectorValue, MultiDimensionalContext};
+use crate::meta_cache;
+use crate::mdd::{BaseCellCollector, VectorValue, MultiDimensionalContext};
 
-use crate::agg_service_client::basic_aggregates;
+use std::collections::HashSet;
+
+use crate::agg_service_client::{basic_aggregates, coordinate_key, CellKey};
 
 pub async fn calculate(
     vs: Vec<TupleVector>,
     context: &mut MultiDimensionalContext,
 ) -> Vec<VectorValue> {
     // Base OlapVectorCoordinates and Formula OlapVectorCoordinates
+    let mut null_indices: Vec<usize> = Vec::new();
     let mut base_indices: Vec<usize> = Vec::new();
     let mut frml_indices: Vec<usize> = Vec::new();
     let mut base_cords: Vec<TupleVector> = Vec::new();
     let mut frml_cords: Vec<TupleVector> = Vec::new();
 
     'outside: for (idx, cord) in vs.into_iter().enumerate() {
+        // 包含空成员的 Tuple 不指向任何单元格，直接返回 Null
+        if cord.member_roles.iter().any(|mr| mr.is_null_member()) {
+            null_indices.push(idx);
+            continue;
+        }
         for mr in &cord.member_roles {
-            if let MemberRole::FormulaMember {
-                dim_role_gid: _,
-                exp: _,
-            } = mr
-            {
+            if let MemberRole::FormulaMember { .. } = mr {
                 frml_indices.push(idx);
                 frml_cords.push(cord);
                 continue 'outside;
@@ -34,20 +39,10 @@ pub async fn calculate(
         base_cords.push(cord);
     }
 
-    let (_cube_gid, base_vals, base_null_flags) = basic_aggregates(base_cords, context).await;
-
-    // let combined: Vec<(f64, bool)> = base_vals.into_iter() .zip(base_null_flags.into_iter()) .collect();
-    let base_combined: Vec<(VectorValue, usize)> = base_vals
+    let base_combined: Vec<(VectorValue, usize)> = fetch_base_cells(base_cords, context)
+        .await
         .into_iter()
-        .zip(base_null_flags.into_iter())
         .zip(base_indices.into_iter())
-        .map(|((val, flag), idx)| {
-            if flag {
-                (VectorValue::Null, idx)
-            } else {
-                (VectorValue::Double(val), idx)
-            }
-        })
         .collect();
 
     let calc_cell_vals = calculate_formula_vectors(frml_cords, context).await;
@@ -58,6 +53,7 @@ pub async fn calculate(
 
     let mut cells_indices = base_combined;
     cells_indices.extend(calc_combined);
+    cells_indices.extend(null_indices.into_iter().map(|idx| (VectorValue::Null, idx)));
     cells_indices.sort_by(|a, b| a.1.cmp(&b.1));
 
     cells_indices
@@ -66,45 +62,188 @@ pub async fn calculate(
         .collect()
 }
 
-async fn calculate_formula_vectors(
-    coordinates: Vec<TupleVector>,
+/// 基本单元格的值。先查本次查询的单元格缓存，收集阶段未命中的坐标只记录下来并返回 Null 占位；
+/// 否则未命中的坐标去重后一次性请求聚合服务，结果写回缓存。
+async fn fetch_base_cells(
+    cords: Vec<TupleVector>,
     context: &mut MultiDimensionalContext,
 ) -> Vec<VectorValue> {
-    let mut values: Vec<VectorValue> = Vec::new();
-
-    'outer_loop: for cord in coordinates {
-        for mr in cord.member_roles.iter().rev() {
-            if let MemberRole::FormulaMember { dim_role_gid, exp } = mr {
-                let dim_role = context
-                    .grpc_client
-                    .get_dimension_role_by_gid(*dim_role_gid)
-                    .await
-                    .unwrap();
-                let member = context
-                    .grpc_client
-                    .get_default_dimension_member_by_dimension_gid(dim_role.dimension_gid)
-                    .await
-                    .unwrap();
-
-                let member_role = MemberRole::BaseMember { dim_role, member };
-                let one_mr_tup = TupleVector {
-                    member_roles: vec![member_role],
-                };
-                let slice_tuple = TupleVector {
-                    member_roles: cord.member_roles.clone(),
+    if let Some(collector) = context.base_cell_collector.as_mut() {
+        let cell_cache = &context.cell_cache;
+        return cords
+            .into_iter()
+            .map(|cord| match cell_cache.get(&coordinate_key(&cord)) {
+                Some(cell_val) => cell_val.clone(),
+                None => {
+                    collector.requests.push(cord);
+                    collector.placeholders += 1;
+                    VectorValue::Null
                 }
-                .merge(&one_mr_tup);
+            })
+            .collect();
+    }
 
-                let exp = exp.clone();
+    let keys: Vec<CellKey> = cords.iter().map(coordinate_key).collect();
+    let mut miss_keys = Vec::new();
+    let mut miss_cords = Vec::new();
+    let mut seen = HashSet::new();
+    for (key, cord) in keys.iter().zip(cords) {
+        if context.cell_cache.contains_key(key) || !seen.insert(key) {
+            continue;
+        }
+        miss_keys.push(key.clone());
+        miss_cords.push(cord);
+    }
 
-                values.push(exp.val(&slice_tuple, context, None).await);
+    if !miss_cords.is_empty() {
+        let fetched = aggregate_base_cells(miss_cords, context).await;
+        context.cell_cache.extend(miss_keys.into_iter().zip(fetched));
+    }
 
-                continue 'outer_loop;
-            }
+    keys.iter().map(|key| context.cell_cache[key].clone()).collect()
+}
+
+async fn aggregate_base_cells(
+    cords: Vec<TupleVector>,
+    context: &MultiDimensionalContext,
+) -> Vec<VectorValue> {
+    let len = cords.len();
+    match basic_aggregates(cords, context).await {
+        Ok((_cube_gid, base_vals, base_null_flags)) => base_vals
+            .into_iter()
+            .zip(base_null_flags.into_iter())
+            .map(|(val, flag)| {
+                if flag {
+                    VectorValue::Null
+                } else {
+                    VectorValue::Double(val)
+                }
+            })
+            .collect(),
+        // 聚合服务不可用时，这些单元格作为错误单元格返回，不中断整个查询
+        Err(msg) => vec![VectorValue::Error(msg); len],
+    }
+}
+
+/// 计算成员单元格分两个阶段求值：先以 Null 占位求值一遍，收集表达式引用的所有基本单元格，
+/// 一次批量取数放入单元格缓存后，只对用到占位值的单元格再正式求值一遍，
+/// 避免每个引用各发一次聚合请求。
+/// 正式求值时因条件分支不同而用到的、缓存中没有的单元格，仍然单独请求。
+async fn calculate_formula_vectors(
+    coordinates: Vec<TupleVector>,
+    context: &mut MultiDimensionalContext,
+) -> Vec<VectorValue> {
+    // 已经在收集阶段或在其它计算成员的求值中时，基本单元格已由最外层统一取数
+    if context.base_cell_collector.is_some() || !context.eval_stack.is_empty() {
+        let mut values: Vec<VectorValue> = Vec::with_capacity(coordinates.len());
+        for cord in &coordinates {
+            values.push(evaluate_formula_vector(cord, context).await);
+        }
+        return values;
+    }
+
+    context.base_cell_collector = Some(BaseCellCollector::default());
+    let mut values: Vec<VectorValue> = Vec::with_capacity(coordinates.len());
+    let mut incomplete: Vec<usize> = Vec::new();
+    for (idx, cord) in coordinates.iter().enumerate() {
+        let placeholders = placeholder_count(context);
+        values.push(evaluate_formula_vector(cord, context).await);
+        if placeholder_count(context) != placeholders {
+            incomplete.push(idx);
         }
-        panic!("[calculate_formula_vectors()] - It's not a formula member role: ______");
     }
+    let collector = context.base_cell_collector.take().unwrap_or_default();
+    fetch_base_cells(collector.requests, context).await;
 
+    for idx in incomplete {
+        values[idx] = evaluate_formula_vector(&coordinates[idx], context).await;
+    }
     values
-    // (context.cube.gid, doubles, bools)
+}
+
+fn placeholder_count(context: &MultiDimensionalContext) -> usize {
+    context
+        .base_cell_collector
+        .as_ref()
+        .map_or(0, |collector| collector.placeholders)
+}
+
+async fn evaluate_formula_vector(
+    cord: &TupleVector,
+    context: &mut MultiDimensionalContext,
+) -> VectorValue {
+    let Some(MemberRole::FormulaMember {
+        dim_role_gid,
+        frml_gid,
+        exp,
+    }) = solving_formula_member(cord, context)
+    else {
+        panic!("[calculate_formula_vectors()] - It's not a formula member role: ______");
+    };
+
+    let dim_role = meta_cache::get_dimension_role_by_gid(*dim_role_gid);
+    let member = meta_cache::get_dimension_default_member(&dim_role);
+
+    let member_role = MemberRole::BaseMember { dim_role, member };
+    let one_mr_tup = TupleVector {
+        member_roles: vec![member_role],
+    };
+    let slice_tuple = TupleVector {
+        member_roles: cord.member_roles.clone(),
+    }
+    .merge(&one_mr_tup);
+
+    let exp = exp.clone();
+
+    evaluate_formula(*dim_role_gid, *frml_gid, &exp, &slice_tuple, cord, context).await
+}
+
+/// 在 slice_tuple 上计算成员的表达式，active_tuple 为检测循环引用时该计算成员所在的 Tuple。
+pub async fn evaluate_formula(
+    dim_role_gid: u64,
+    frml_gid: u64,
+    exp: &AstExpression,
+    slice_tuple: &TupleVector,
+    active_tuple: &TupleVector,
+    context: &mut MultiDimensionalContext,
+) -> VectorValue {
+    if let Err(err_val) = context.enter_formula(dim_role_gid, frml_gid, active_tuple) {
+        return err_val;
+    }
+    let cell_val = exp.val(slice_tuple, context, None).await;
+    context.exit_formula();
+    cell_val
+}
+
+/// 单元格的 FORMAT_STRING：优先使用求解顺序最高的计算成员的格式，
+/// 它没有设置格式时使用 Tuple 中基本成员（通常是度量）的 FORMAT_STRING 属性。
+pub fn cell_format_string(cord: &TupleVector, context: &MultiDimensionalContext) -> Option<String> {
+    if let Some(format_string) =
+        solving_formula_member(cord, context).and_then(|mr| mr.format_string(context))
+    {
+        return Some(format_string);
+    }
+    cord.member_roles
+        .iter()
+        .filter(|mr| matches!(mr, MemberRole::BaseMember { member, .. } if !member.is_null()))
+        .find_map(|mr| mr.format_string(context))
+}
+
+/// 多个计算成员相交时，SOLVE_ORDER 最大的计算成员最后求解，即最先展开它的表达式，
+/// 其它计算成员在它的表达式中再求解。SOLVE_ORDER 相同时取维度角色 gid 较大的计算成员，
+/// 结果与计算成员在 Tuple 中的顺序无关。
+fn solving_formula_member<'a>(
+    cord: &'a TupleVector,
+    context: &MultiDimensionalContext,
+) -> Option<&'a MemberRole> {
+    cord.member_roles
+        .iter()
+        .filter_map(|mr| match mr {
+            MemberRole::FormulaMember { dim_role_gid, .. } => {
+                Some((mr.solve_order(context).unwrap(), *dim_role_gid, mr))
+            }
+            _ => None,
+        })
+        .max_by_key(|(solve_order, dim_role_gid, _)| (*solve_order, *dim_role_gid))
+        .map(|(_, _, mr)| mr)
 }
@@ -0,0 +1,431 @@
+use once_cell::sync::Lazy;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cfg::get_cfg;
+
+/// 1970-01-01 对应的儒略日数（Julian Day Number）
+const UNIX_EPOCH_JULIAN_DAY: i64 = 2_440_588;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// 日期值，内部保存为距离 1970-01-01 的天数（公历，不含时区）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OlapDate {
+    days: i64,
+}
+
+impl OlapDate {
+    pub fn from_days(days: i64) -> Self {
+        OlapDate { days }
+    }
+
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(OlapDate {
+            days: days_from_civil(year, month, day),
+        })
+    }
+
+    /// 解析 `yyyy-mm-dd` 格式的日期字符串，也接受 `yyyy/mm/dd` 与 `yyyymmdd`。
+    pub fn parse(date_str: &str) -> Option<Self> {
+        let date_str = date_str.trim();
+
+        let parts: Vec<&str> = if date_str.contains('-') {
+            date_str.split('-').collect()
+        } else if date_str.contains('/') {
+            date_str.split('/').collect()
+        } else if date_str.len() == 8 && date_str.chars().all(|c| c.is_ascii_digit()) {
+            vec![&date_str[0..4], &date_str[4..6], &date_str[6..8]]
+        } else {
+            return None;
+        };
+
+        if parts.len() != 3 {
+            return None;
+        }
+
+        let year = parts[0].parse::<i64>().ok()?;
+        let month = parts[1].parse::<u32>().ok()?;
+        let day = parts[2].parse::<u32>().ok()?;
+        OlapDate::from_ymd(year, month, day)
+    }
+
+    /// UNIX 时间（秒）转换为日期，不足一天的部分被舍去。
+    pub fn from_unix_seconds(seconds: i64) -> Self {
+        OlapDate {
+            days: seconds.div_euclid(SECONDS_PER_DAY),
+        }
+    }
+
+    pub fn from_julian_day(julian_day: i64) -> Self {
+        OlapDate {
+            days: julian_day - UNIX_EPOCH_JULIAN_DAY,
+        }
+    }
+
+    pub fn days(&self) -> i64 {
+        self.days
+    }
+
+    pub fn unix_seconds(&self) -> i64 {
+        self.days * SECONDS_PER_DAY
+    }
+
+    pub fn julian_day(&self) -> i64 {
+        self.days + UNIX_EPOCH_JULIAN_DAY
+    }
+
+    pub fn year(&self) -> i64 {
+        civil_from_days(self.days).0
+    }
+
+    pub fn month(&self) -> u32 {
+        civil_from_days(self.days).1
+    }
+
+    pub fn day(&self) -> u32 {
+        civil_from_days(self.days).2
+    }
+
+    pub fn quarter(&self) -> u32 {
+        (self.month() - 1) / 3 + 1
+    }
+
+    /// 一年中的第几天，从 1 开始
+    pub fn day_of_year(&self) -> u32 {
+        (self.days - days_from_civil(self.year(), 1, 1) + 1) as u32
+    }
+
+    /// 星期几，1 表示星期日，7 表示星期六
+    pub fn weekday(&self) -> u32 {
+        // 1970-01-01 是星期四
+        ((self.days + 4).rem_euclid(7) + 1) as u32
+    }
+
+    /// 一年中的第几周，1 月 1 日所在的周为第 1 周，每周从星期日开始
+    pub fn week_of_year(&self) -> u32 {
+        let jan_1st = OlapDate::from_days(days_from_civil(self.year(), 1, 1));
+        (self.day_of_year() + jan_1st.weekday() - 2) / 7 + 1
+    }
+
+    /// 日期所在时间段的第一天
+    pub fn period_start(&self, granularity: DateGranularity) -> OlapDate {
+        let (year, month, _) = civil_from_days(self.days);
+        match granularity {
+            DateGranularity::Year => OlapDate::from_days(days_from_civil(year, 1, 1)),
+            DateGranularity::HalfYear => {
+                OlapDate::from_days(days_from_civil(year, if month <= 6 { 1 } else { 7 }, 1))
+            }
+            DateGranularity::Quarter => {
+                OlapDate::from_days(days_from_civil(year, (month - 1) / 3 * 3 + 1, 1))
+            }
+            DateGranularity::Month => OlapDate::from_days(days_from_civil(year, month, 1)),
+            DateGranularity::Week => OlapDate::from_days(self.days - (self.weekday() as i64 - 1)),
+            DateGranularity::Day => *self,
+        }
+    }
+
+    /// 日期所在时间段的最后一天
+    pub fn period_end(&self, granularity: DateGranularity) -> OlapDate {
+        let next_period = self.period_start(granularity).roll(granularity, 1);
+        OlapDate::from_days(next_period.days - 1)
+    }
+
+    /// 按指定的时间单位前后移动日期。按月移动时，如果目标月份没有对应的日，则取该月最后一天。
+    pub fn roll(&self, granularity: DateGranularity, count: i64) -> OlapDate {
+        match granularity {
+            DateGranularity::Day => OlapDate::from_days(self.days + count),
+            DateGranularity::Week => OlapDate::from_days(self.days + count * 7),
+            DateGranularity::Month => self.add_months(count),
+            DateGranularity::Quarter => self.add_months(count * 3),
+            DateGranularity::HalfYear => self.add_months(count * 6),
+            DateGranularity::Year => self.add_months(count * 12),
+        }
+    }
+
+    fn add_months(&self, count: i64) -> OlapDate {
+        let (year, month, day) = civil_from_days(self.days);
+        let month_index = year * 12 + (month as i64 - 1) + count;
+        let new_year = month_index.div_euclid(12);
+        let new_month = (month_index.rem_euclid(12) + 1) as u32;
+        let new_day = day.min(days_in_month(new_year, new_month));
+        OlapDate::from_days(days_from_civil(new_year, new_month, new_day))
+    }
+
+    /// `other - self` 跨越的时间单位个数（按时间段边界计算，例如 1 月 31 日到 2 月 1 日相差 1 个月）。
+    pub fn diff(&self, other: &OlapDate, granularity: DateGranularity) -> i64 {
+        let (y1, m1, _) = civil_from_days(self.days);
+        let (y2, m2, _) = civil_from_days(other.days);
+        let month_diff = (y2 * 12 + m2 as i64) - (y1 * 12 + m1 as i64);
+        match granularity {
+            DateGranularity::Day => other.days - self.days,
+            DateGranularity::Week => {
+                (other.period_start(DateGranularity::Week).days
+                    - self.period_start(DateGranularity::Week).days)
+                    / 7
+            }
+            DateGranularity::Month => month_diff,
+            DateGranularity::Quarter => {
+                (y2 * 4 + other.quarter() as i64) - (y1 * 4 + self.quarter() as i64)
+            }
+            DateGranularity::HalfYear => {
+                (y2 * 2 + (m2 as i64 - 1) / 6) - (y1 * 2 + (m1 as i64 - 1) / 6)
+            }
+            DateGranularity::Year => y2 - y1,
+        }
+    }
+
+    /// 按格式字符串输出日期。
+    ///
+    /// 支持的占位符：`yyyy` `yy` `mmmm`（月份全称） `mmm`（月份缩写） `mm` `m` `dd` `d` `q`（季度），
+    /// 其它字符原样输出。占位符不区分大小写。
+    pub fn format(&self, pattern: &str) -> String {
+        const TOKENS: [&str; 9] = ["yyyy", "yy", "mmmm", "mmm", "mm", "m", "dd", "d", "q"];
+
+        let (year, month, day) = civil_from_days(self.days);
+        let lower_pattern = pattern.to_ascii_lowercase();
+        let mut result = String::new();
+        let mut pos = 0;
+
+        while pos < pattern.len() {
+            let rest = &lower_pattern[pos..];
+            match TOKENS.iter().find(|token| rest.starts_with(*token)) {
+                Some(token) => {
+                    let text = match *token {
+                        "yyyy" => format!("{:04}", year),
+                        "yy" => format!("{:02}", year.rem_euclid(100)),
+                        "mmmm" => MONTH_NAMES[month as usize - 1].to_string(),
+                        "mmm" => MONTH_NAMES[month as usize - 1][..3].to_string(),
+                        "mm" => format!("{:02}", month),
+                        "m" => format!("{}", month),
+                        "dd" => format!("{:02}", day),
+                        "d" => format!("{}", day),
+                        _ => format!("{}", self.quarter()),
+                    };
+                    result.push_str(&text);
+                    pos += token.len();
+                }
+                None => {
+                    let ch = pattern[pos..].chars().next().unwrap();
+                    result.push(ch);
+                    pos += ch.len_utf8();
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl fmt::Display for OlapDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.days);
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// 时间维度中层级的日期粒度，同时用作日期函数中的时间单位（DP_YEAR、DP_MONTH 等）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateGranularity {
+    Year,
+    HalfYear,
+    Quarter,
+    Month,
+    Week,
+    Day,
+}
+
+impl DateGranularity {
+    /// 解析日期粒度，不区分大小写，可以带 `DP_` 前缀，例如 `DP_MONTH`、`month`。
+    pub fn parse(granularity: &str) -> Option<Self> {
+        let granularity = granularity.trim().to_ascii_uppercase();
+        let granularity = granularity.strip_prefix("DP_").unwrap_or(&granularity);
+        match granularity {
+            "YEAR" | "YEARS" => Some(DateGranularity::Year),
+            "HALFYEAR" | "HALF_YEAR" => Some(DateGranularity::HalfYear),
+            "QUARTER" | "QUARTERS" => Some(DateGranularity::Quarter),
+            "MONTH" | "MONTHS" => Some(DateGranularity::Month),
+            "WEEK" | "WEEKS" => Some(DateGranularity::Week),
+            "DAY" | "DAYS" => Some(DateGranularity::Day),
+            _ => None,
+        }
+    }
+}
+
+// 配置文件中的 today 用于固定 Today() 的返回值，便于测试与回放历史报表
+static FIXED_TODAY: Lazy<Option<OlapDate>> = Lazy::new(|| {
+    get_cfg().today.as_ref().map(|today| {
+        OlapDate::parse(today)
+            .unwrap_or_else(|| panic!("[cal-001] Invalid `today` in config.toml: {}", today))
+    })
+});
+
+/// 当前日期（UTC）。如果配置了 today，则始终返回配置的日期。
+pub fn today() -> OlapDate {
+    if let Some(today) = *FIXED_TODAY {
+        return today;
+    }
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    OlapDate::from_unix_seconds(seconds)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ => {
+            if is_leap_year(year) {
+                29
+            } else {
+                28
+            }
+        }
+    }
+}
+
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date_str: &str) -> OlapDate {
+        OlapDate::parse(date_str).unwrap()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        assert_eq!(date("2024-02-29").to_string(), "2024-02-29");
+        assert_eq!(date("2024/3/5").to_string(), "2024-03-05");
+        assert_eq!(date("20241231").to_string(), "2024-12-31");
+        assert_eq!(OlapDate::parse("2023-02-29"), None);
+        assert_eq!(OlapDate::parse("2024-13-01"), None);
+        assert_eq!(OlapDate::parse("2024-01"), None);
+        assert_eq!(OlapDate::parse("not a date"), None);
+
+        let d = date("2024-03-05");
+        assert_eq!(d.format("yyyy-mm-dd"), "2024-03-05");
+        assert_eq!(d.format("yy/m/d"), "24/3/5");
+        assert_eq!(d.format("MMMM d, yyyy"), "March 5, 2024");
+        assert_eq!(d.format("mmm yyyy-q"), "Mar 2024-1");
+    }
+
+    #[test]
+    fn day_count_round_trip() {
+        assert_eq!(date("1970-01-01").days(), 0);
+        assert_eq!(date("1969-12-31").days(), -1);
+        assert_eq!(date("2000-03-01").days(), 11_017);
+
+        for days in -800_000..800_000 {
+            let d = OlapDate::from_days(days);
+            let (year, month, day) = (d.year(), d.month(), d.day());
+            assert_eq!(OlapDate::from_ymd(year, month, day), Some(d));
+        }
+
+        let d = date("2024-02-29");
+        assert_eq!(OlapDate::from_unix_seconds(d.unix_seconds() + 3_600), d);
+        assert_eq!(OlapDate::from_julian_day(d.julian_day()), d);
+        assert_eq!(date("2000-01-01").julian_day(), 2_451_545);
+    }
+
+    #[test]
+    fn granularity_bucketing() {
+        // 2024-08-15 是星期四
+        let d = date("2024-08-15");
+        assert_eq!(d.weekday(), 5);
+        assert_eq!(d.period_start(DateGranularity::Year), date("2024-01-01"));
+        assert_eq!(
+            d.period_start(DateGranularity::HalfYear),
+            date("2024-07-01")
+        );
+        assert_eq!(d.period_start(DateGranularity::Quarter), date("2024-07-01"));
+        assert_eq!(d.period_start(DateGranularity::Month), date("2024-08-01"));
+        assert_eq!(d.period_start(DateGranularity::Week), date("2024-08-11"));
+        assert_eq!(d.period_start(DateGranularity::Day), d);
+        assert_eq!(d.period_end(DateGranularity::Quarter), date("2024-09-30"));
+        assert_eq!(
+            date("2024-02-10").period_end(DateGranularity::Month),
+            date("2024-02-29")
+        );
+
+        assert_eq!(
+            date("2024-01-31").roll(DateGranularity::Month, 1),
+            date("2024-02-29")
+        );
+        assert_eq!(
+            date("2024-02-29").roll(DateGranularity::Year, -1),
+            date("2023-02-28")
+        );
+        assert_eq!(
+            date("2024-01-31").diff(&date("2024-02-01"), DateGranularity::Month),
+            1
+        );
+        assert_eq!(
+            date("2023-12-31").diff(&date("2024-01-01"), DateGranularity::Year),
+            1
+        );
+        assert_eq!(
+            date("2024-06-30").diff(&date("2024-07-01"), DateGranularity::HalfYear),
+            1
+        );
+
+        assert_eq!(
+            DateGranularity::parse("DP_MONTH"),
+            Some(DateGranularity::Month)
+        );
+        assert_eq!(
+            DateGranularity::parse(" quarter "),
+            Some(DateGranularity::Quarter)
+        );
+        assert_eq!(DateGranularity::parse("decade"), None);
+    }
+}
@@ -0,0 +1,254 @@
+// FORMAT_STRING 单元格格式化
+
+use crate::mdd::VectorValue;
+
+/// 按 FORMAT_STRING 格式化单元格值。
+///
+/// 格式字符串最多四段，以 `;` 分隔，依次用于正数、负数、零和 Null：
+/// 只有一段时用于所有数值，负数前加 `-`；负数段为空时使用第一段；Null 没有对应段时为空字符串。
+/// 除数字格式（`0`、`#`、`,`、`.`、`%`）外，还支持命名格式
+/// `General Number`、`Currency`、`Fixed`、`Standard` 和 `Percent`。
+/// 日期值按 FormatDate 的规则格式化，字符串原样返回。
+pub fn format_cell(cell_val: &VectorValue, format_string: Option<&str>) -> String {
+    let format_string = match format_string {
+        Some(fs) if !fs.trim().is_empty() => fs,
+        _ => return default_format(cell_val),
+    };
+
+    let sections = split_sections(format_string);
+    match cell_val {
+        VectorValue::Double(num) => {
+            let num = *num;
+            match (sections.get(1), sections.get(2)) {
+                (_, Some(zero_sec)) if num == 0.0 && !zero_sec.is_empty() => {
+                    format_number(0.0, zero_sec)
+                }
+                (Some(neg_sec), _) if num < 0.0 && !neg_sec.is_empty() => {
+                    format_number(-num, neg_sec)
+                }
+                _ => {
+                    let text = format_number(num.abs(), &sections[0]);
+                    if num < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+                        format!("-{}", text)
+                    } else {
+                        text
+                    }
+                }
+            }
+        }
+        VectorValue::Null => match sections.get(3) {
+            Some(null_sec) => unquote(null_sec),
+            None => String::new(),
+        },
+        VectorValue::Date(date) => date.format(&sections[0]),
+        _ => default_format(cell_val),
+    }
+}
+
+/// 没有 FORMAT_STRING 时的单元格文本，与原始值的字符串形式一致。
+pub fn default_format(cell_val: &VectorValue) -> String {
+    match cell_val {
+        VectorValue::Double(num) => format!("{}", num),
+        VectorValue::Str(str) => str.clone(),
+        VectorValue::Date(date) => date.to_string(),
+        VectorValue::Null => String::new(),
+        VectorValue::Invalid => String::from("Invalid"),
+        VectorValue::Error(msg) => msg.clone(),
+    }
+}
+
+/// 按 `;` 分段，引号和反斜杠转义中的 `;` 不作为分隔符。
+fn split_sections(format_string: &str) -> Vec<String> {
+    let mut sections: Vec<String> = vec![String::new()];
+    let mut in_quotes = false;
+    let mut chars = format_string.chars();
+    while let Some(c) = chars.next() {
+        let section = sections.last_mut().unwrap();
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                section.push(c);
+            }
+            '\\' => {
+                section.push(c);
+                if let Some(escaped) = chars.next() {
+                    section.push(escaped);
+                }
+            }
+            ';' if !in_quotes => sections.push(String::new()),
+            _ => section.push(c),
+        }
+    }
+    sections
+}
+
+fn named_format(name: &str) -> Option<&'static str> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "currency" => Some("$#,##0.00"),
+        "fixed" => Some("0.00"),
+        "standard" => Some("#,##0.00"),
+        "percent" => Some("0.00%"),
+        _ => None,
+    }
+}
+
+/// 按一个格式段格式化非负数值。
+fn format_number(num: f64, section: &str) -> String {
+    if section.trim().eq_ignore_ascii_case("general number") {
+        return format!("{}", num);
+    }
+    let section = named_format(section).unwrap_or(section);
+
+    // 数字占位符之前和之后的部分作为字面文本
+    let mut prefix = String::new();
+    let mut pattern = String::new();
+    let mut suffix = String::new();
+    let mut percent = false;
+    let mut in_quotes = false;
+    let mut chars = section.chars().peekable();
+    while let Some(c) = chars.next() {
+        // 数字格式必须连续，出现后缀文本之后的占位符按字面文本处理
+        let in_number = suffix.is_empty();
+        let literal = if in_quotes {
+            if c == '"' {
+                in_quotes = false;
+                continue;
+            }
+            Some(c)
+        } else {
+            match c {
+                '"' => {
+                    in_quotes = true;
+                    continue;
+                }
+                '\\' => chars.next(),
+                '0' | '#' if in_number => {
+                    pattern.push(c);
+                    None
+                }
+                ',' | '.' if in_number && matches!(chars.peek(), Some('0' | '#' | ',' | '.')) => {
+                    pattern.push(c);
+                    None
+                }
+                '%' => {
+                    percent = true;
+                    Some(c)
+                }
+                _ => Some(c),
+            }
+        };
+        if let Some(literal) = literal {
+            if pattern.is_empty() {
+                prefix.push(literal);
+            } else {
+                suffix.push(literal);
+            }
+        }
+    }
+
+    if pattern.is_empty() {
+        return format!("{}{}", prefix, suffix);
+    }
+
+    let num = if percent { num * 100.0 } else { num };
+    let (int_pattern, dec_pattern) = match pattern.split_once('.') {
+        Some((int_pattern, dec_pattern)) => (int_pattern, dec_pattern.replace(['.', ','], "")),
+        None => (pattern.as_str(), String::new()),
+    };
+    let grouping = int_pattern.contains(',');
+    let min_int_digits = int_pattern.chars().filter(|c| *c == '0').count();
+    let min_dec_digits = dec_pattern.chars().filter(|c| *c == '0').count();
+    let max_dec_digits = dec_pattern.len();
+
+    let rounded = format!("{:.*}", max_dec_digits, num);
+    let (int_digits, dec_digits) = match rounded.split_once('.') {
+        Some((int_digits, dec_digits)) => (int_digits.to_string(), dec_digits.to_string()),
+        None => (rounded, String::new()),
+    };
+
+    let mut int_digits = int_digits.trim_start_matches('0').to_string();
+    while int_digits.len() < min_int_digits {
+        int_digits.insert(0, '0');
+    }
+    if grouping {
+        int_digits = group_thousands(&int_digits);
+    }
+
+    let mut dec_digits = dec_digits;
+    while dec_digits.len() > min_dec_digits && dec_digits.ends_with('0') {
+        dec_digits.pop();
+    }
+
+    if dec_digits.is_empty() {
+        format!("{}{}{}", prefix, int_digits, suffix)
+    } else {
+        format!("{}{}.{}{}", prefix, int_digits, dec_digits, suffix)
+    }
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (idx, c) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+fn unquote(section: &str) -> String {
+    let mut text = String::new();
+    let mut chars = section.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {}
+            '\\' => text.extend(chars.next()),
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_cell;
+    use crate::mdd::VectorValue;
+
+    fn fmt(num: f64, format_string: &str) -> String {
+        format_cell(&VectorValue::Double(num), Some(format_string))
+    }
+
+    #[test]
+    fn thousands_and_decimals() {
+        assert_eq!(fmt(1234567.891, "#,##0.00"), "1,234,567.89");
+        assert_eq!(fmt(0.5, "#,##0.00"), "0.50");
+        assert_eq!(fmt(-1234.5, "#,##0.00"), "-1,234.50");
+    }
+
+    #[test]
+    fn percent() {
+        assert_eq!(fmt(0.1234, "0.00%"), "12.34%");
+        assert_eq!(fmt(1.0, "0.00%"), "100.00%");
+    }
+
+    #[test]
+    fn named_currency() {
+        assert_eq!(fmt(1234.5, "Currency"), "$1,234.50");
+        assert_eq!(fmt(-1234.5, "Currency"), "-$1,234.50");
+    }
+
+    #[test]
+    fn four_sections() {
+        let format_string = r#"#,##0;(#,##0);"Zero";"N/A""#;
+        assert_eq!(fmt(1234.0, format_string), "1,234");
+        assert_eq!(fmt(-1234.0, format_string), "(1,234)");
+        assert_eq!(fmt(0.0, format_string), "Zero");
+        assert_eq!(format_cell(&VectorValue::Null, Some(format_string)), "N/A");
+    }
+
+    #[test]
+    fn negative_rounded_to_zero_has_no_sign() {
+        assert_eq!(fmt(-0.001, "0.00"), "0.00");
+    }
+}
@@ -1,3 +1,4 @@
+use once_cell::sync::Lazy;
 use serde::Deserialize;
 use std::env;
 use std::fs;
@@ -5,8 +6,37 @@ use std::fs;
 #[derive(Default, Deserialize)]
 pub struct Config {
     pub meta_grpc_url: String,
+    // 固定 Today() 的返回值（yyyy-mm-dd），未配置时使用系统时钟
+    pub today: Option<String>,
+    // 计算成员嵌套求值的最大深度，超出时单元格返回错误，未配置时为 DEFAULT_MAX_EVAL_DEPTH
+    pub max_eval_depth: Option<usize>,
+    // 聚合服务地址，未配置时为 DEFAULT_AGG_GRPC_URL
+    pub agg_grpc_url: Option<String>,
+    // 单次聚合请求的超时时间（毫秒）和失败后的最大重试次数
+    pub agg_timeout_ms: Option<u64>,
+    pub agg_max_retries: Option<u32>,
+    // 跨查询的单元格值缓存和整条语句结果缓存的容量（字节），未配置或为 0 时不缓存
+    pub cell_cache_max_bytes: Option<usize>,
+    pub statement_cache_max_bytes: Option<usize>,
+    // 缓存的已解析语句条数，未配置时为 DEFAULT_PARSED_STATEMENT_CACHE_SIZE，为 0 时不缓存
+    pub parsed_statement_cache_size: Option<usize>,
+    // 定期从元数据服务重新加载元数据的间隔（秒），未配置或为 0 时不定期加载
+    pub meta_reload_interval_secs: Option<u64>,
+    // 订阅元数据服务的变化通知，收到通知后立即重新加载
+    pub meta_watch_changes: Option<bool>,
+    // 本地元数据快照文件的路径。配置后每次加载到新的元数据时写入这个文件，
+    // 启动时先从文件加载并立即提供服务，再在后台从元数据服务刷新
+    pub meta_snapshot_path: Option<String>,
+    // 可以执行 RELOAD_METADATA 等管理操作的用户，未配置时任何用户都不能执行
+    pub admin_users: Option<Vec<String>>,
 }
 
+pub const DEFAULT_MAX_EVAL_DEPTH: usize = 256;
+pub const DEFAULT_AGG_GRPC_URL: &str = "http://127.0.0.1:16060";
+pub const DEFAULT_AGG_TIMEOUT_MS: u64 = 30_000;
+pub const DEFAULT_AGG_MAX_RETRIES: u32 = 2;
+pub const DEFAULT_PARSED_STATEMENT_CACHE_SIZE: usize = 1024;
+
 #[derive(Deserialize)]
 pub struct Settings {
     #[serde(default)]
@@ -23,7 +53,14 @@ impl Settings {
     }
 }
 
-pub fn get_cfg() -> Config {
+// 配置文件只在第一次使用时读取和解析，修改 config.toml 后需要重启服务
+static CONFIG: Lazy<Config> = Lazy::new(load_cfg);
+
+pub fn get_cfg() -> &'static Config {
+    &CONFIG
+}
+
+fn load_cfg() -> Config {
     let settings = Settings::load("config.toml").expect("Failed to load config");
 
     let env_var = env::var("OLAP_ENV");
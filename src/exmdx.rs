@@ -6,3 +6,4 @@ pub mod lv_func;
 pub mod mdd;
 pub mod mem_func;
 pub mod set_func;
+pub mod tup_func;
@@ -14,7 +14,7 @@ use crate::permission::UserAccessesCollection;
 use crate::exmdx::exp_func::AstExpFunction;
 use crate::exmdx::mem_func::AstMemberFunction;
 
-use crate::calcul::calculate;
+use crate::calcul::{calculate, evaluate_formula};
 
 use crate::exmdx::logic::AstBoolExp;
 use crate::exmdx::lv_func::AstLevelFunction;
@@ -23,11 +23,18 @@ use crate::exmdx::set_func::AstSetFunction;
 
 use crate::exmdx::hier_func::AstHierarchyFunction;
 
+use crate::exmdx::tup_func::AstTupleFunction;
+
+use crate::mdx_grammar::SetFragmentParser;
+use crate::mdx_lexer::Lexer as MdxLexer;
+
 use core::panic;
 use std::collections::HashMap;
 
-use crate::cfg::get_cfg;
+use crate::cfg::{get_cfg, DEFAULT_MAX_EVAL_DEPTH};
 use crate::olapmeta_grpc_client::GrpcClient;
+use crate::meta_cache;
+use crate::plan_cache;
 
 pub trait Materializable {
     fn materialize<'a>(
@@ -88,17 +95,29 @@ impl Materializable for AstSegsObj {
             }
 
             if is_formula_member {
-                let dim_role_gid = self.get_first_gid().unwrap();
-
-                let cus_obj: AstCustomObject = context
-                    .formulas_map
-                    .get(&last_opt.unwrap())
-                    .unwrap()
-                    .clone();
-                if let AstCustomObject::FormulaMember(_, exp) = cus_obj {
-                    // let AstCustomObject::FormulaMember(_, exp) =
-                    //     context.formulas_map.get(&last_opt.unwrap()).unwrap().clone();
-                    return MultiDimensionalEntity::FormulaMemberWrap { dim_role_gid, exp };
+                // 唯一名称 [DimRole].&[gid] 以维度角色名称开头
+                let dim_role_gid = match self.segs.first() {
+                    Some(AstSeg::Str(dim_role_name)) => {
+                        meta_cache::find_dimension_role_by_name(context.cube.gid, dim_role_name)
+                            .map(|dim_role| dim_role.gid)
+                    }
+                    _ => self.get_first_gid(),
+                }
+                .unwrap_or_else(|| {
+                    panic!(
+                        "[un-002] DimensionRole of formula member {:?} not found.",
+                        self.segs
+                    )
+                });
+
+                let frml_gid = last_opt.unwrap();
+                let cus_obj: AstCustomObject = context.formulas_map.get(&frml_gid).unwrap().clone();
+                if let AstCustomObject::FormulaMember(_, exp, _) = cus_obj {
+                    return MultiDimensionalEntity::FormulaMemberWrap {
+                        dim_role_gid,
+                        frml_gid,
+                        exp,
+                    };
                 } else {
                     todo!("[NVB676] MemberRoleWrap is not implemented yet.")
                 }
@@ -123,16 +142,28 @@ impl Materializable for AstSegsObj {
                         .await
                 }
                 MultiDimensionalEntity::MemberRoleWrap(member_role) => {
-                    if self.segs.len() == 1 {
-                        return MultiDimensionalEntity::MemberRoleWrap(member_role);
-                    }
-                    todo!("[NVB676] MemberRoleWrap is not implemented yet.")
+                    let tail_segs = AstSegsObj {
+                        segs: (self.segs[1..]).to_vec(),
+                    };
+                    member_role
+                        .locate_entity(&tail_segs, slice_tuple, context)
+                        .await
                 }
                 MultiDimensionalEntity::LevelRole(lv_role) => {
-                    if self.segs.len() == 1 {
-                        return MultiDimensionalEntity::LevelRole(lv_role);
-                    }
-                    todo!("[NVB666DC] MemberRoleWrap is not implemented yet.")
+                    let tail_segs = AstSegsObj {
+                        segs: (self.segs[1..]).to_vec(),
+                    };
+                    lv_role
+                        .locate_member(&tail_segs, slice_tuple, context)
+                        .await
+                }
+                MultiDimensionalEntity::HierarchyRole(hier_role) => {
+                    let tail_segs = AstSegsObj {
+                        segs: (self.segs[1..]).to_vec(),
+                    };
+                    hier_role
+                        .locate_entity(&tail_segs, slice_tuple, context)
+                        .await
                 }
                 MultiDimensionalEntity::Cube(cube) => {
                     if self.segs.len() == 1 {
@@ -151,6 +182,10 @@ impl Materializable for AstSegsObj {
                     };
                     set.locate_entity(&tail_segs, slice_tuple, context).await
                 }
+                // 函数求值出错时（例如 Dimensions(99)）不再继续定位，错误作为单元格值返回
+                MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)) => {
+                    MultiDimensionalEntity::VectorValue(VectorValue::Error(msg))
+                }
                 _ => {
                     panic!("In method AstSegsObj::materialize(): head_entity is not a DimensionRoleWrap!");
                 }
@@ -169,6 +204,7 @@ pub enum AstSeg {
     ExpFunc(AstExpFunction),
     LevelFunc(AstLevelFunction),
     HierarchyFunc(AstHierarchyFunction),
+    TupleFunc(AstTupleFunction),
 }
 
 impl AstSeg {
@@ -204,12 +240,18 @@ impl Materializable for AstSeg {
                     let exp_val = exp_fn.val(slice_tuple, context, None).await;
                     MultiDimensionalEntity::VectorValue(exp_val)
                 }
-                AstSeg::SetFunc(set_fn) => {
-                    let set = set_fn.get_set(None, slice_tuple, context).await;
-                    MultiDimensionalEntity::SetWrap(set)
+                AstSeg::SetFunc(set_fn) => match set_fn.get_set(None, slice_tuple, context).await {
+                    Ok(set) => MultiDimensionalEntity::SetWrap(set),
+                    Err(msg) => MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)),
+                },
+                Self::HierarchyFunc(hier_fn) => {
+                    hier_fn.get_entity(None, slice_tuple, context).await
                 }
-                Self::HierarchyFunc(_hier_func) => {
-                    todo!("[NVBh-5676] HierarchyFunc is not implemented yet.")
+                AstSeg::TupleFunc(tup_fn) => {
+                    match tup_fn.get_tuple(None, slice_tuple, context).await {
+                        Ok(tuple) => MultiDimensionalEntity::TupleWrap(tuple),
+                        Err(msg) => MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)),
+                    }
                 }
             }
         })
@@ -248,12 +290,9 @@ impl AstMdxStatement {
         let cube;
 
         let config = get_cfg();
-        println!("< 1 > config.meta_grpc_url: {:#?}", config.meta_grpc_url);
 
-        // 创建 gRPC 客户端
-        let mut grpc_cli = GrpcClient::new(config.meta_grpc_url)
-            .await
-            .expect("Failed to create client");
+        // 共享的元数据服务连接
+        let grpc_cli = GrpcClient::get_cli().await;
 
         // 如果没有 ast_seg，直接 panic
         let ast_seg = match ast_seg_opt {
@@ -263,41 +302,43 @@ impl AstMdxStatement {
 
         match ast_seg {
             AstSeg::Gid(gid) => {
-                cube = self.fetch_cube_by_gid(&mut grpc_cli, *gid).await;
+                cube = self.fetch_cube_by_gid(*gid);
             }
             AstSeg::Str(seg_str) => {
-                cube = self.fetch_cube_by_name(&mut grpc_cli, &seg_str).await;
+                cube = self.fetch_cube_by_name(seg_str);
             }
             AstSeg::GidStr(gid, _) => {
-                cube = self.fetch_cube_by_gid(&mut grpc_cli, *gid).await;
+                cube = self.fetch_cube_by_gid(*gid);
             }
             _ => panic!("The entity is not a Gid or a Str variant. 2"),
         }
 
-        let mut cube_def_tuple = TupleVector {
-            member_roles: Vec::new(),
-        };
+        let cube_def_tuple = match plan_cache::cube_default_tuple(cube.gid) {
+            Some(cube_def_tuple) => cube_def_tuple,
+            None => {
+                let mut cube_def_tuple = TupleVector {
+                    member_roles: Vec::new(),
+                };
 
-        let dimension_roles = grpc_cli
-            .get_dimension_roles_by_cube_gid(cube.gid)
-            .await
-            .unwrap();
-        for dim_role in dimension_roles {
-            let dim_def_member = grpc_cli
-                .get_default_dimension_member_by_dimension_gid(dim_role.dimension_gid)
-                .await
-                .unwrap();
-
-            cube_def_tuple.member_roles.push(MemberRole::BaseMember {
-                dim_role,
-                member: dim_def_member,
-            });
-        }
+                let dimension_roles = meta_cache::get_dimension_roles_by_cube_gid(cube.gid);
+                for dim_role in dimension_roles {
+                    let dim_def_member = meta_cache::get_dimension_default_member(&dim_role);
+
+                    cube_def_tuple.member_roles.push(MemberRole::BaseMember {
+                        dim_role,
+                        member: dim_def_member,
+                    });
+                }
+
+                plan_cache::put_cube_default_tuple(cube.gid, cube_def_tuple.clone());
+                cube_def_tuple
+            }
+        };
 
         let mut formulas_map: HashMap<u64, AstCustomObject> = HashMap::new();
         for frml_obj in &self.custom_objs {
             match frml_obj {
-                AstCustomObject::FormulaMember(segments, _exp) => {
+                AstCustomObject::FormulaMember(segments, _exp, _props) => {
                     let frml_member_gid = segments.get_last_gid().unwrap();
                     formulas_map.insert(frml_member_gid, frml_obj.clone());
                     // let frml_member_gid = segments.get_last_gid().unwrap();
@@ -319,6 +360,10 @@ impl AstMdxStatement {
             grpc_client: grpc_cli,
             formulas_map,
             user_acol,
+            eval_stack: vec![],
+            max_eval_depth: config.max_eval_depth.unwrap_or(DEFAULT_MAX_EVAL_DEPTH),
+            base_cell_collector: None,
+            cell_cache: HashMap::new(),
         };
 
         let mut where_tuple: Option<TupleVector> = None;
@@ -340,57 +385,24 @@ impl AstMdxStatement {
         context
     }
 
-    async fn fetch_cube_by_gid(&self, grpc_cli: &mut GrpcClient, gid: u64) -> Cube {
-        match grpc_cli.get_cube_by_gid(gid).await {
-            Ok(response) => response
-                .cube_meta
-                .map(|meta| Cube {
-                    gid: meta.gid,
-                    name: meta.name,
-                })
-                .unwrap_or_else(|| {
-                    println!("Error fetching Cube by GID: CubeMeta is None");
-                    Cube {
-                        gid: 0,
-                        name: String::from(">>> No cube found <<<"),
-                    }
-                }),
-            Err(e) => {
-                println!("Error fetching Cube by GID: {}", e);
-                Cube {
-                    gid: 0,
-                    name: String::from(">>> No cube found <<<"),
-                }
+    fn fetch_cube_by_gid(&self, gid: u64) -> Cube {
+        meta_cache::find_cube_by_gid(gid).unwrap_or_else(|| {
+            println!("Error fetching Cube by GID: cube {} not found", gid);
+            Cube {
+                gid: 0,
+                name: String::from(">>> No cube found <<<"),
             }
-        }
+        })
     }
 
-    async fn fetch_cube_by_name(&self, grpc_cli: &mut GrpcClient, name: &str) -> Cube {
-        match grpc_cli.get_cube_by_name(name.to_string()).await {
-            Ok(response) => {
-                println!("Received Cube by Name: {:?}", response);
-                response
-                    .cube_meta
-                    .map(|meta| Cube {
-                        gid: meta.gid,
-                        name: meta.name,
-                    })
-                    .unwrap_or_else(|| {
-                        println!("Error fetching Cube by Name: CubeMeta is None");
-                        Cube {
-                            gid: 0,
-                            name: String::from(">>> No cube found <<<"),
-                        }
-                    })
+    fn fetch_cube_by_name(&self, name: &str) -> Cube {
+        meta_cache::find_cube_by_name(name).unwrap_or_else(|| {
+            println!("Error fetching Cube by Name: cube [{}] not found", name);
+            Cube {
+                gid: 0,
+                name: String::from(">>> No cube found <<<"),
             }
-            Err(e) => {
-                println!("Error fetching Cube by Name: {}", e);
-                Cube {
-                    gid: 0,
-                    name: String::from(">>> No cube found <<<"),
-                }
-            }
-        }
+        })
     }
 
     pub async fn build_axes(&self, context: &mut MultiDimensionalContext) -> Vec<Axis> {
@@ -425,6 +437,49 @@ pub enum AstSet {
 }
 
 impl AstSet {
+    /// 将 MemberToStr、TupleToStr、SetToStr 生成的字符串重新解析为 AstSet。
+    ///
+    /// 字符串可以是一个成员、一个元组或者一个用 `{}` 包围的集合，按 SetFragment 规则解析，
+    /// 字符串中不能出现查询语句的其它部分（例如 `} on 0 from ...`）。
+    /// 字符串来自报表参数等用户输入，无法解析时返回错误而不是 panic。
+    pub fn parse_from_str(mdx_str: &str) -> Result<AstSet, String> {
+        SetFragmentParser::new()
+            .parse(MdxLexer::new(mdx_str))
+            .map_err(|err| {
+                format!(
+                    "[str-001] AstSet::parse_from_str() can not parse '{}': {:?}",
+                    mdx_str.trim(),
+                    err
+                )
+            })
+    }
+
+    /// StrToMember、StrToTuple、StrToSet 的公共部分：
+    /// 计算字符串表达式，解析并在当前上下文中物化为 Set。
+    /// 参数不是字符串或字符串无法解析时返回错误，由调用方作为错误单元格值返回。
+    pub async fn materialize_from_str(
+        str_exp: &AstExpression,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> Result<Set, String> {
+        let mdx_str = match str_exp.val(slice_tuple, context, None).await {
+            VectorValue::Str(mdx_str) => mdx_str,
+            other => {
+                return Err(format!(
+                    "[str-002] The parameter of StrToMember/StrToTuple/StrToSet must be a string, but got {:?}",
+                    other
+                ))
+            }
+        };
+
+        let ast_set = AstSet::parse_from_str(&mdx_str)?;
+        match ast_set.materialize(slice_tuple, context).await {
+            MultiDimensionalEntity::SetWrap(set) => Ok(set),
+            MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)) => Err(msg),
+            _ => Err(format!("[str-003] '{}' is not a set.", mdx_str)),
+        }
+    }
+
     async fn generate_fiducial_tuple(
         &self,
         slice_tuple: &TupleVector,
@@ -441,6 +496,10 @@ impl AstSet {
                     .await
                 {
                     MultiDimensionalEntity::TupleWrap(tuple) => tuple.clone(),
+                    // 出错的集合由 translate_to_axis() 报告
+                    MultiDimensionalEntity::VectorValue(VectorValue::Error(_)) => TupleVector {
+                        member_roles: vec![],
+                    },
                     _ => panic!("The entity is not a TupleWrap variant."),
                 };
             }
@@ -448,9 +507,38 @@ impl AstSet {
                 let olap_entity = segs_obj.materialize(slice_tuple, context).await;
                 // println!("olap_entity: {:#?}", olap_entity);
                 match olap_entity {
-                    MultiDimensionalEntity::FormulaMemberWrap { dim_role_gid, exp } => {
+                    MultiDimensionalEntity::FormulaMemberWrap {
+                        dim_role_gid,
+                        frml_gid,
+                        exp,
+                    } => {
+                        result = TupleVector {
+                            member_roles: vec![MemberRole::FormulaMember {
+                                dim_role_gid,
+                                frml_gid,
+                                exp,
+                            }],
+                        };
+                    }
+                    MultiDimensionalEntity::MemberRoleWrap(member_role) => {
+                        result = TupleVector {
+                            member_roles: vec![member_role],
+                        };
+                    }
+                    MultiDimensionalEntity::TupleWrap(tuple) => {
+                        result = tuple;
+                    }
+                    MultiDimensionalEntity::SetWrap(set) => {
+                        result = match set.tuples.into_iter().next() {
+                            Some(tuple) => tuple,
+                            None => TupleVector {
+                                member_roles: vec![],
+                            },
+                        };
+                    }
+                    MultiDimensionalEntity::VectorValue(VectorValue::Error(_)) => {
                         result = TupleVector {
-                            member_roles: vec![MemberRole::FormulaMember { dim_role_gid, exp }],
+                            member_roles: vec![],
                         };
                     }
                     _ => panic!("The entity is not a FormulaMemberWrap variant."),
@@ -478,6 +566,12 @@ impl Materializable for AstSet {
                             MultiDimensionalEntity::TupleWrap(tuple) => {
                                 tuple_vec.push(tuple);
                             }
+                            // StrToTuple 等函数出错时，错误交给调用方处理
+                            MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)) => {
+                                return MultiDimensionalEntity::VectorValue(VectorValue::Error(
+                                    msg,
+                                ));
+                            }
                             _ => {
                                 panic!("The entity is not a TupleWrap variant.");
                             }
@@ -487,11 +581,16 @@ impl Materializable for AstSet {
                 AstSet::SegsObj(segs_obj) => {
                     let olap_entity = segs_obj.materialize(slice_tuple, context).await;
                     match olap_entity {
-                        MultiDimensionalEntity::FormulaMemberWrap { dim_role_gid, exp } => {
+                        MultiDimensionalEntity::FormulaMemberWrap {
+                            dim_role_gid,
+                            frml_gid,
+                            exp,
+                        } => {
                             return MultiDimensionalEntity::SetWrap(Set {
                                 tuples: vec![TupleVector {
                                     member_roles: vec![MemberRole::FormulaMember {
                                         dim_role_gid,
+                                        frml_gid,
                                         exp,
                                     }],
                                 }],
@@ -502,6 +601,17 @@ impl Materializable for AstSet {
                                 tuple_vec.push(tuple.clone());
                             }
                         }
+                        MultiDimensionalEntity::MemberRoleWrap(member_role) => {
+                            tuple_vec.push(TupleVector {
+                                member_roles: vec![member_role],
+                            });
+                        }
+                        MultiDimensionalEntity::TupleWrap(tuple) => {
+                            tuple_vec.push(tuple);
+                        }
+                        MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)) => {
+                            return MultiDimensionalEntity::VectorValue(VectorValue::Error(msg));
+                        }
                         _ => {
                             panic!("The entity is not a SetWrap variant.");
                         }
@@ -536,8 +646,24 @@ impl Materializable for AstTuple {
                             MultiDimensionalEntity::MemberRoleWrap(member_role) => {
                                 member_roles.push(member_role);
                             }
-                            MultiDimensionalEntity::FormulaMemberWrap { dim_role_gid, exp } => {
-                                member_roles.push(MemberRole::FormulaMember { dim_role_gid, exp });
+                            MultiDimensionalEntity::FormulaMemberWrap {
+                                dim_role_gid,
+                                frml_gid,
+                                exp,
+                            } => {
+                                member_roles.push(MemberRole::FormulaMember {
+                                    dim_role_gid,
+                                    frml_gid,
+                                    exp,
+                                });
+                            }
+                            MultiDimensionalEntity::TupleWrap(tuple) => {
+                                member_roles.extend(tuple.member_roles);
+                            }
+                            MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)) => {
+                                return MultiDimensionalEntity::VectorValue(VectorValue::Error(
+                                    msg,
+                                ));
                             }
                             _ => {
                                 panic!("The entity is not a MemberRoleWrap variant.");
@@ -558,13 +684,23 @@ impl Materializable for AstTuple {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstCustomObject {
-    FormulaMember(AstSegsObj, AstExpression),
+    FormulaMember(AstSegsObj, AstExpression, AstFormulaMemberProps),
     CustomSet(AstSegsObj, AstSet),
 }
 
+/// 计算成员定义之后的属性，例如 `MEMBER ... AS <exp>, SOLVE_ORDER = 10, FORMAT_STRING = "#,##0.00"`。
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct AstFormulaMemberProps {
+    // 默认为 0
+    pub solve_order: i32,
+    pub format_string: Option<String>,
+}
+
 // #[derive(Clone, Debug, PartialEq)]
 pub struct AstAxis {
     pub ast_set: AstSet,
+    // DIMENSION PROPERTIES 子句中请求的成员属性名称
+    pub dim_properties: Vec<String>,
     pub position: u64,
 }
 
@@ -592,8 +728,13 @@ impl AstAxis {
                 axis = Axis {
                     set,
                     pos_num: self.position as u32,
+                    dim_properties: self.dim_properties.clone(),
                 };
             }
+            // 轴上没有可以返回错误单元格值的位置，整个查询失败
+            MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)) => {
+                panic!("[ax-001] Axis {} can not be built: {}", self.position, msg);
+            }
             _ => {
                 panic!("The entity is not a SetWrap variant.");
             }
@@ -608,6 +749,26 @@ pub struct AstExpression {
     pub terms: Vec<(char, AstTerm)>,
 }
 
+impl AstExpression {
+    /// 表达式只由一个成员或 Tuple（不含运算符）构成时返回该因子，IS 运算按对象比较。
+    pub fn as_olap_object(&self) -> Option<&AstFactory> {
+        let [(_, term)] = self.terms.as_slice() else {
+            return None;
+        };
+        match term.factories.as_slice() {
+            [(_, factory @ (AstFactory::AstSegsObj(_) | AstFactory::AstTuple(_)))] => Some(factory),
+            _ => None,
+        }
+    }
+
+    pub fn is_null_literal(&self) -> bool {
+        match self.terms.as_slice() {
+            [(_, term)] => matches!(term.factories.as_slice(), [(_, AstFactory::Null)]),
+            _ => false,
+        }
+    }
+}
+
 impl ToVectorValue for AstExpression {
     fn val<'a>(
         &'a self,
@@ -627,6 +788,7 @@ impl ToVectorValue for AstExpression {
                 match *op {
                     '+' => result = result + term_value,
                     '-' => result = result - term_value,
+                    '&' => result = result.concat(term_value),
                     _ => panic!("Invalid operator in AstExpression: {}", op),
                 }
             }
@@ -643,6 +805,11 @@ pub enum AstFactory {
     AstTuple(AstTuple),
     AstExpression(AstExpression),
     AstCaseStatement(AstCaseStatement),
+    Null,
+    // 一元运算符 '+' 或 '-'
+    Unary(char, Box<AstFactory>),
+    // 底数 ^ 指数
+    Power(Box<AstFactory>, Box<AstFactory>),
 }
 
 impl ToVectorValue for AstFactory {
@@ -671,13 +838,31 @@ impl ToVectorValue for AstFactory {
                             cell_values.first().unwrap().clone()
                         }
                         MultiDimensionalEntity::FormulaMemberWrap {
-                            dim_role_gid: _,
+                            dim_role_gid,
+                            frml_gid,
                             exp,
-                        } => exp.val(slice_tuple, context, None).await,
+                        } => {
+                            evaluate_formula(
+                                dim_role_gid,
+                                frml_gid,
+                                &exp,
+                                slice_tuple,
+                                slice_tuple,
+                                context,
+                            )
+                            .await
+                        }
                         // MultiDimensionalEntity::ExpFn(exp_fn) => {
                         //     exp_fn.val(slice_tuple, context, None).await
                         // }
                         MultiDimensionalEntity::VectorValue(cell_value) => cell_value.clone(),
+                        MultiDimensionalEntity::TupleWrap(olap_tuple) => {
+                            let ovc = TupleVector {
+                                member_roles: slice_tuple.merge(&olap_tuple).member_roles,
+                            };
+                            let cell_values = calculate(vec![ovc], context).await;
+                            cell_values.first().unwrap().clone()
+                        }
                         _ => panic!("The entity is not a VectorValue variant."),
                     }
                 }
@@ -690,6 +875,7 @@ impl ToVectorValue for AstFactory {
                             let cell_values = calculate(vec![ovc], context).await;
                             cell_values.first().unwrap().clone()
                         }
+                        MultiDimensionalEntity::VectorValue(cell_value) => cell_value,
                         _ => panic!("The entity is not a TupleWrap variant."),
                     }
                 }
@@ -697,6 +883,20 @@ impl ToVectorValue for AstFactory {
                 AstFactory::AstCaseStatement(case_stmt) => {
                     case_stmt.val(slice_tuple, context, None).await
                 }
+                AstFactory::Null => VectorValue::Null,
+                AstFactory::Unary(op, factory) => {
+                    let value = factory.val(slice_tuple, context, None).await;
+                    match *op {
+                        '-' => -value,
+                        '+' => value.unary_plus(),
+                        _ => panic!("Invalid unary operator in AstFactory: {}", op),
+                    }
+                }
+                AstFactory::Power(base, exponent) => {
+                    let base_value = base.val(slice_tuple, context, None).await;
+                    let exponent_value = exponent.val(slice_tuple, context, None).await;
+                    base_value.pow(exponent_value)
+                }
             }
         })
     }
@@ -726,6 +926,7 @@ impl ToVectorValue for AstTerm {
                 match *op {
                     '*' => result = result * factory_value,
                     '/' => result = result / factory_value,
+                    '%' => result = result % factory_value,
                     _ => panic!("Invalid operator in AstTerm: {}", op),
                 }
             }
@@ -759,3 +960,45 @@ impl ToVectorValue for AstCaseStatement {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AstSet, AstTuple};
+
+    #[test]
+    fn parse_from_str_accepts_members_tuples_and_sets() {
+        assert_eq!(AstSet::parse_from_str(" { } "), Ok(AstSet::Tuples(vec![])));
+        for (mdx_str, tuples) in [
+            ("[a]", 1),
+            ("([a], [b])", 1),
+            ("[a], ([b], [c])", 2),
+            ("{[a], [b], [c]}", 3),
+        ] {
+            match AstSet::parse_from_str(mdx_str) {
+                Ok(AstSet::Tuples(parsed)) => assert_eq!(parsed.len(), tuples, "{}", mdx_str),
+                other => panic!("{}: {:?}", mdx_str, other),
+            }
+        }
+        match AstSet::parse_from_str("([a], [b])") {
+            Ok(AstSet::Tuples(parsed)) => {
+                assert!(matches!(&parsed[0], AstTuple::SegsObjects(segs) if segs.len() == 2))
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_from_str_rejects_other_statement_parts() {
+        for mdx_str in [
+            "[a]} on 0 from &500000000000002 where ([b]",
+            "{[a]} on 0 from [c]",
+            "select {[a]} on 0 from [c]",
+            "[a]]",
+        ] {
+            match AstSet::parse_from_str(mdx_str) {
+                Err(msg) => assert!(msg.starts_with("[str-001]"), "{}", msg),
+                other => panic!("{} is accepted: {:?}", mdx_str, other),
+            }
+        }
+    }
+}
@@ -10,8 +10,7 @@ use crate::calcul;
 
 use core::panic;
 
-use crate::mdx_grammar::MdxStatementParser;
-use crate::mdx_lexer::Lexer as MdxLexer;
+use crate::plan_cache;
 
 use crate::exmdx::ast::{AstSegsObj, Materializable};
 
@@ -23,6 +22,8 @@ use crate::meta_cache;
 
 use crate::exmdx::ast::AstExpression;
 
+use crate::calendar::{self, DateGranularity, OlapDate};
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstExpFunction {
     Avg(AstNumFnAvg),
@@ -47,6 +48,32 @@ pub enum AstExpFunction {
     Rank(AstNumFnRank),
     Stdev(AstNumFnStdev),
     Var(AstNumFnVar),
+    Upper(AstStrFnUpper),
+    Lower(AstStrFnLower),
+    Left(AstStrFnLeft),
+    Right(AstStrFnRight),
+    Substring(AstStrFnSubstring),
+    Concat(AstStrFnConcat),
+    LTrim(AstStrFnLTrim),
+    RTrim(AstStrFnRTrim),
+    NumToStr(AstStrFnNumToStr),
+    Len(AstNumFnLen),
+    InStr(AstNumFnInStr),
+    StrToNum(AstNumFnStrToNum),
+    UniqueName(AstStrFnUniqueName),
+    Properties(AstStrFnProperties),
+    MemberToStr(AstStrFnMemberToStr),
+    TupleToStr(AstStrFnTupleToStr),
+    SetToStr(AstStrFnSetToStr),
+    Today(AstDateFnToday),
+    DateDiff(AstNumFnDateDiff),
+    DatePart(AstNumFnDatePart),
+    DateRoll(AstDateFnDateRoll),
+    FormatDate(AstStrFnFormatDate),
+    GetFirstDate(AstDateFnGetFirstDate),
+    GetLastDate(AstDateFnGetLastDate),
+    JulianDate(AstNumFnJulianDate),
+    UnixDate(AstDateFnUnixDate),
 }
 
 impl ToVectorValue for AstExpFunction {
@@ -114,6 +141,80 @@ impl ToVectorValue for AstExpFunction {
                     exp_fn.val(slice_tuple, context, outer_param).await
                 }
                 AstExpFunction::Var(exp_fn) => exp_fn.val(slice_tuple, context, outer_param).await,
+                AstExpFunction::Upper(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::Lower(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::Left(exp_fn) => exp_fn.val(slice_tuple, context, outer_param).await,
+                AstExpFunction::Right(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::Substring(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::Concat(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::LTrim(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::RTrim(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::NumToStr(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::Len(exp_fn) => exp_fn.val(slice_tuple, context, outer_param).await,
+                AstExpFunction::InStr(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::StrToNum(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::UniqueName(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::Properties(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::MemberToStr(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::TupleToStr(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::SetToStr(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::Today(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::DateDiff(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::DatePart(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::DateRoll(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::FormatDate(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::GetFirstDate(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::GetLastDate(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::JulianDate(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
+                AstExpFunction::UnixDate(exp_fn) => {
+                    exp_fn.val(slice_tuple, context, outer_param).await
+                }
             }
         })
     }
@@ -242,27 +343,30 @@ impl ToVectorValue for AstExpFnLookupCube {
                 }
             }
 
-            let mdx_with_str = meta_cache::mdx_formula_members_fragment(&param_cube);
-            let tunnel_mdx = format!(
-                "with\n{}\nSelect {{ ( &0 ) }} on rows\nfrom &{}",
-                mdx_with_str, param_cube.gid
-            );
+            // 收集阶段不在另一个 Cube 上做真正的聚合，以 Null 占位，正式求值时再计算
+            if let Some(collector) = context.base_cell_collector.as_mut() {
+                collector.placeholders += 1;
+                return VectorValue::Null;
+            }
 
-            let tunnel_ast = MdxStatementParser::new()
-                .parse(MdxLexer::new(&tunnel_mdx))
-                .unwrap();
+            let tunnel_ast = match plan_cache::lookup_cube_statement(&param_cube) {
+                Ok(tunnel_ast) => tunnel_ast,
+                Err(msg) => return VectorValue::Error(msg),
+            };
             let mut tunnel_context = tunnel_ast.gen_md_context(context.user_acol.clone()).await;
             // [warning] !!! look above code, the method - 'context.user_acol.clone()' may cause performance issue.
             // please consider to use '&context.user_acol'
 
-
-            param_exp
+            context.enter_tunnel(&mut tunnel_context);
+            let cell_val = param_exp
                 .val(
                     &tunnel_context.query_slice_tuple.clone(),
                     &mut tunnel_context,
                     None,
                 )
-                .await
+                .await;
+            context.exit_tunnel(&mut tunnel_context);
+            cell_val
         })
     }
 }
@@ -644,3 +748,912 @@ impl ToVectorValue for AstNumFnVar {
         Box::pin(async move { VectorValue::Null })
     }
 }
+
+/// 将字符串函数的参数值转换为 String。
+///
+/// 数值按 `{}` 格式输出；`Null` 与 `Invalid` 原样返回给调用方作为函数结果。
+fn str_param(cell_val: VectorValue) -> Result<String, VectorValue> {
+    match cell_val {
+        VectorValue::Str(str) => Ok(str),
+        VectorValue::Double(num) => Ok(format!("{}", num)),
+        VectorValue::Date(date) => Ok(date.to_string()),
+        other => Err(other),
+    }
+}
+
+/// 将数值参数转换为 i64，非数值参数返回 `Invalid`。
+fn int_param(cell_val: VectorValue) -> Result<i64, VectorValue> {
+    match cell_val {
+        VectorValue::Double(num) => Ok(num as i64),
+        VectorValue::Null => Err(VectorValue::Null),
+        _ => Err(VectorValue::Invalid),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnUpper {
+    pub str_exp: AstExpression,
+}
+
+impl ToVectorValue for AstStrFnUpper {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => VectorValue::Str(str.to_uppercase()),
+                Err(cell_val) => cell_val,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnLower {
+    pub str_exp: AstExpression,
+}
+
+impl ToVectorValue for AstStrFnLower {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => VectorValue::Str(str.to_lowercase()),
+                Err(cell_val) => cell_val,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnLeft {
+    pub str_exp: AstExpression,
+    pub len_exp: AstExpression,
+}
+
+impl ToVectorValue for AstStrFnLeft {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let str = match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => str,
+                Err(cell_val) => return cell_val,
+            };
+            let len = match int_param(self.len_exp.val(slice_tuple, context, None).await) {
+                Ok(len) => len.max(0) as usize,
+                Err(cell_val) => return cell_val,
+            };
+            // 按字符（而不是字节）截取，保证中文成员名不会被截断
+            VectorValue::Str(str.chars().take(len).collect())
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnRight {
+    pub str_exp: AstExpression,
+    pub len_exp: AstExpression,
+}
+
+impl ToVectorValue for AstStrFnRight {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let str = match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => str,
+                Err(cell_val) => return cell_val,
+            };
+            let len = match int_param(self.len_exp.val(slice_tuple, context, None).await) {
+                Ok(len) => len.max(0) as usize,
+                Err(cell_val) => return cell_val,
+            };
+            let char_count = str.chars().count();
+            VectorValue::Str(str.chars().skip(char_count.saturating_sub(len)).collect())
+        })
+    }
+}
+
+/// Substring ( string, start [, end ] )
+///
+/// `start` 与 `end` 都是从 0 开始的字符位置，结果包含 `start`、不包含 `end`；
+/// 省略 `end` 时截取到字符串末尾，越界的位置会被收敛到字符串长度以内。
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnSubstring {
+    pub str_exp: AstExpression,
+    pub start_exp: AstExpression,
+    pub end_exp: Option<AstExpression>,
+}
+
+impl ToVectorValue for AstStrFnSubstring {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let str = match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => str,
+                Err(cell_val) => return cell_val,
+            };
+            let char_count = str.chars().count() as i64;
+            let start = match int_param(self.start_exp.val(slice_tuple, context, None).await) {
+                Ok(start) => start.clamp(0, char_count),
+                Err(cell_val) => return cell_val,
+            };
+            let end = match &self.end_exp {
+                Some(end_exp) => match int_param(end_exp.val(slice_tuple, context, None).await) {
+                    Ok(end) => end.clamp(start, char_count),
+                    Err(cell_val) => return cell_val,
+                },
+                None => char_count,
+            };
+            VectorValue::Str(
+                str.chars()
+                    .skip(start as usize)
+                    .take((end - start) as usize)
+                    .collect(),
+            )
+        })
+    }
+}
+
+/// Concat ( string1, string2 [, ...] )，`Null` 参数按空字符串拼接。
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnConcat {
+    pub exps: Vec<AstExpression>,
+}
+
+impl ToVectorValue for AstStrFnConcat {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let mut result = String::new();
+            for exp in &self.exps {
+                match str_param(exp.val(slice_tuple, context, None).await) {
+                    Ok(str) => result.push_str(&str),
+                    Err(VectorValue::Null) => {}
+                    Err(cell_val) => return cell_val,
+                }
+            }
+            VectorValue::Str(result)
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnLTrim {
+    pub str_exp: AstExpression,
+}
+
+impl ToVectorValue for AstStrFnLTrim {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => VectorValue::Str(str.trim_start().to_string()),
+                Err(cell_val) => cell_val,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnRTrim {
+    pub str_exp: AstExpression,
+}
+
+impl ToVectorValue for AstStrFnRTrim {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => VectorValue::Str(str.trim_end().to_string()),
+                Err(cell_val) => cell_val,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnNumToStr {
+    pub num_exp: AstExpression,
+}
+
+impl ToVectorValue for AstStrFnNumToStr {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match self.num_exp.val(slice_tuple, context, None).await {
+                VectorValue::Double(num) => VectorValue::Str(format!("{}", num)),
+                VectorValue::Null => VectorValue::Null,
+                _ => VectorValue::Invalid,
+            }
+        })
+    }
+}
+
+/// Len ( string )，返回字符数（不是字节数）。
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstNumFnLen {
+    pub str_exp: AstExpression,
+}
+
+impl ToVectorValue for AstNumFnLen {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => VectorValue::Double(str.chars().count() as f64),
+                Err(cell_val) => cell_val,
+            }
+        })
+    }
+}
+
+/// InStr ( string, search_string [, start ] )
+///
+/// 返回 `search_string` 在 `string` 中第一次出现的字符位置（从 1 开始），
+/// 未找到时返回 0；`start` 同样从 1 开始，表示开始查找的位置。
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstNumFnInStr {
+    pub str_exp: AstExpression,
+    pub search_exp: AstExpression,
+    pub start_exp: Option<AstExpression>,
+}
+
+impl ToVectorValue for AstNumFnInStr {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let str = match str_param(self.str_exp.val(slice_tuple, context, None).await) {
+                Ok(str) => str,
+                Err(cell_val) => return cell_val,
+            };
+            let search = match str_param(self.search_exp.val(slice_tuple, context, None).await) {
+                Ok(search) => search,
+                Err(cell_val) => return cell_val,
+            };
+            let skip = match &self.start_exp {
+                Some(start_exp) => match int_param(start_exp.val(slice_tuple, context, None).await)
+                {
+                    Ok(start) => (start.max(1) - 1) as usize,
+                    Err(cell_val) => return cell_val,
+                },
+                None => 0,
+            };
+
+            let byte_offset = match str.char_indices().nth(skip) {
+                Some((offset, _)) => offset,
+                None => return VectorValue::Double(0.0),
+            };
+            match str[byte_offset..].find(&search) {
+                Some(found) => {
+                    let char_pos = str[..byte_offset + found].chars().count();
+                    VectorValue::Double((char_pos + 1) as f64)
+                }
+                None => VectorValue::Double(0.0),
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstNumFnStrToNum {
+    pub str_exp: AstExpression,
+}
+
+impl ToVectorValue for AstNumFnStrToNum {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match self.str_exp.val(slice_tuple, context, None).await {
+                VectorValue::Str(str) => match str.trim().parse::<f64>() {
+                    Ok(num) => VectorValue::Double(num),
+                    Err(_) => VectorValue::Invalid,
+                },
+                VectorValue::Double(num) => VectorValue::Double(num),
+                VectorValue::Null => VectorValue::Null,
+                VectorValue::Error(msg) => VectorValue::Error(msg),
+                VectorValue::Date(_) | VectorValue::Invalid => VectorValue::Invalid,
+            }
+        })
+    }
+}
+
+/// 生成成员实体的唯一名称，非成员实体返回 `Invalid`。
+fn member_unique_name(
+    olap_obj: MultiDimensionalEntity,
+    context: &MultiDimensionalContext,
+) -> VectorValue {
+    match olap_obj {
+        MultiDimensionalEntity::MemberRoleWrap(member_role) => {
+            VectorValue::Str(member_role.unique_name(context))
+        }
+        MultiDimensionalEntity::FormulaMemberWrap {
+            dim_role_gid,
+            frml_gid,
+            exp,
+        } => VectorValue::Str(
+            MemberRole::FormulaMember {
+                dim_role_gid,
+                frml_gid,
+                exp,
+            }
+            .unique_name(context),
+        ),
+        _ => VectorValue::Invalid,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AstStrFnUniqueName {
+    Chain,
+    SegsObj(AstSegsObj),
+}
+
+impl ToVectorValue for AstStrFnUniqueName {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let param_olap_obj = match self {
+                AstStrFnUniqueName::SegsObj(segs) => segs.materialize(slice_tuple, context).await,
+                AstStrFnUniqueName::Chain => {
+                    if let Some(olap_obj) = outer_param {
+                        olap_obj
+                    } else {
+                        panic!("[dsuc-0-fff3] AstStrFnUniqueName::val()")
+                    }
+                }
+            };
+
+            member_unique_name(param_olap_obj, context)
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnProperties {
+    pub name_exp: AstExpression,
+    pub typed: bool,
+}
+
+impl ToVectorValue for AstStrFnProperties {
+    /// 属性不存在时返回 Null；指定 TYPED 时，数值和日期属性按其类型返回，否则返回字符串。
+    /// 左侧不是成员或属性名称不是字符串时返回错误单元格值。
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let member_role = match outer_param {
+                Some(MultiDimensionalEntity::MemberRoleWrap(member_role)) => member_role,
+                Some(MultiDimensionalEntity::FormulaMemberWrap {
+                    dim_role_gid,
+                    frml_gid,
+                    exp,
+                }) => MemberRole::FormulaMember {
+                    dim_role_gid,
+                    frml_gid,
+                    exp,
+                },
+                Some(MultiDimensionalEntity::VectorValue(VectorValue::Error(msg))) => {
+                    return VectorValue::Error(msg)
+                }
+                other => {
+                    return VectorValue::Error(format!(
+                        "[pr-001] Properties expects a member expression on its left, but got {:?}",
+                        other
+                    ))
+                }
+            };
+
+            let name = match self.name_exp.val(slice_tuple, context, None).await {
+                VectorValue::Str(name) => name,
+                VectorValue::Null => return VectorValue::Null,
+                VectorValue::Error(msg) => return VectorValue::Error(msg),
+                other => {
+                    return VectorValue::Error(format!(
+                        "[pr-002] Properties expects a property name string, but got {:?}",
+                        other
+                    ))
+                }
+            };
+
+            match member_role.property(&name, context) {
+                None => VectorValue::Null,
+                Some(prop_val) if self.typed => {
+                    if let Ok(num) = prop_val.trim().parse::<f64>() {
+                        VectorValue::Double(num)
+                    } else if let Some(date) = OlapDate::parse(prop_val.trim()) {
+                        VectorValue::Date(date)
+                    } else {
+                        VectorValue::Str(prop_val)
+                    }
+                }
+                Some(prop_val) => VectorValue::Str(prop_val),
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnMemberToStr {
+    pub member_segs: AstSegsObj,
+}
+
+impl ToVectorValue for AstStrFnMemberToStr {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let olap_obj = self.member_segs.materialize(slice_tuple, context).await;
+            member_unique_name(olap_obj, context)
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnTupleToStr {
+    pub ast_tup: AstTuple,
+}
+
+impl ToVectorValue for AstStrFnTupleToStr {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match self.ast_tup.materialize(slice_tuple, context).await {
+                MultiDimensionalEntity::TupleWrap(tuple) => {
+                    VectorValue::Str(tuple.to_mdx_str(context))
+                }
+                _ => VectorValue::Invalid,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnSetToStr {
+    pub ast_set: AstSet,
+}
+
+impl ToVectorValue for AstStrFnSetToStr {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match self.ast_set.materialize(slice_tuple, context).await {
+                MultiDimensionalEntity::SetWrap(set) => VectorValue::Str(set.to_mdx_str(context)),
+                _ => VectorValue::Invalid,
+            }
+        })
+    }
+}
+
+/// 将日期函数的参数值转换为 OlapDate。
+///
+/// 数值按 UNIX 时间（秒）处理，字符串按 `yyyy-mm-dd` 解析；`Null` 原样返回，其它返回 `Invalid`。
+fn date_param(cell_val: VectorValue) -> Result<OlapDate, VectorValue> {
+    match cell_val {
+        VectorValue::Date(date) => Ok(date),
+        VectorValue::Double(seconds) => Ok(OlapDate::from_unix_seconds(seconds as i64)),
+        VectorValue::Str(str) => OlapDate::parse(&str).ok_or(VectorValue::Invalid),
+        VectorValue::Null => Err(VectorValue::Null),
+        VectorValue::Invalid => Err(VectorValue::Invalid),
+        VectorValue::Error(msg) => Err(VectorValue::Error(msg)),
+    }
+}
+
+/// 将时间单位参数（DP_YEAR、DP_QUARTER、DP_MONTH、DP_WEEK、DP_DAY 等）转换为 DateGranularity。
+fn date_part_param(cell_val: VectorValue) -> Result<DateGranularity, VectorValue> {
+    match cell_val {
+        VectorValue::Str(str) => DateGranularity::parse(&str).ok_or(VectorValue::Invalid),
+        VectorValue::Null => Err(VectorValue::Null),
+        _ => Err(VectorValue::Invalid),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstDateFnToday {}
+
+impl ToVectorValue for AstDateFnToday {
+    fn val<'a>(
+        &'a self,
+        _slice_tuple: &'a TupleVector,
+        _context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move { VectorValue::Date(calendar::today()) })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstNumFnDateDiff {
+    pub date_exp_1: AstExpression,
+    pub date_exp_2: AstExpression,
+    pub part_exp: AstExpression,
+}
+
+impl ToVectorValue for AstNumFnDateDiff {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let date_1 = match date_param(self.date_exp_1.val(slice_tuple, context, None).await) {
+                Ok(date) => date,
+                Err(cell_val) => return cell_val,
+            };
+            let date_2 = match date_param(self.date_exp_2.val(slice_tuple, context, None).await) {
+                Ok(date) => date,
+                Err(cell_val) => return cell_val,
+            };
+            let granularity =
+                match date_part_param(self.part_exp.val(slice_tuple, context, None).await) {
+                    Ok(granularity) => granularity,
+                    Err(cell_val) => return cell_val,
+                };
+
+            VectorValue::Double(date_1.diff(&date_2, granularity) as f64)
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstNumFnDatePart {
+    pub date_exp: AstExpression,
+    pub part_exp: AstExpression,
+}
+
+impl ToVectorValue for AstNumFnDatePart {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let date = match date_param(self.date_exp.val(slice_tuple, context, None).await) {
+                Ok(date) => date,
+                Err(cell_val) => return cell_val,
+            };
+
+            let part = match self.part_exp.val(slice_tuple, context, None).await {
+                VectorValue::Str(part) => part.trim().to_ascii_uppercase(),
+                VectorValue::Null => return VectorValue::Null,
+                _ => return VectorValue::Invalid,
+            };
+
+            // DP_DAYOFYEAR 与 DP_WEEKDAY 只用于 DatePart，不是时间单位
+            match part.strip_prefix("DP_").unwrap_or(&part) {
+                "DAYOFYEAR" => return VectorValue::Double(date.day_of_year() as f64),
+                "WEEKDAY" => return VectorValue::Double(date.weekday() as f64),
+                _ => {}
+            }
+
+            match DateGranularity::parse(&part) {
+                Some(DateGranularity::Year) => VectorValue::Double(date.year() as f64),
+                Some(DateGranularity::HalfYear) => {
+                    VectorValue::Double(if date.month() <= 6 { 1.0 } else { 2.0 })
+                }
+                Some(DateGranularity::Quarter) => VectorValue::Double(date.quarter() as f64),
+                Some(DateGranularity::Month) => VectorValue::Double(date.month() as f64),
+                Some(DateGranularity::Week) => VectorValue::Double(date.week_of_year() as f64),
+                Some(DateGranularity::Day) => VectorValue::Double(date.day() as f64),
+                None => VectorValue::Invalid,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstDateFnDateRoll {
+    pub date_exp: AstExpression,
+    pub part_exp: AstExpression,
+    pub count_exp: AstExpression,
+}
+
+impl ToVectorValue for AstDateFnDateRoll {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let date = match date_param(self.date_exp.val(slice_tuple, context, None).await) {
+                Ok(date) => date,
+                Err(cell_val) => return cell_val,
+            };
+            let granularity =
+                match date_part_param(self.part_exp.val(slice_tuple, context, None).await) {
+                    Ok(granularity) => granularity,
+                    Err(cell_val) => return cell_val,
+                };
+            let count = match int_param(self.count_exp.val(slice_tuple, context, None).await) {
+                Ok(count) => count,
+                Err(cell_val) => return cell_val,
+            };
+
+            VectorValue::Date(date.roll(granularity, count))
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStrFnFormatDate {
+    pub date_exp: AstExpression,
+    pub format_exp: AstExpression,
+}
+
+impl ToVectorValue for AstStrFnFormatDate {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let date = match date_param(self.date_exp.val(slice_tuple, context, None).await) {
+                Ok(date) => date,
+                Err(cell_val) => return cell_val,
+            };
+            match self.format_exp.val(slice_tuple, context, None).await {
+                VectorValue::Str(pattern) => VectorValue::Str(date.format(&pattern)),
+                VectorValue::Null => VectorValue::Null,
+                _ => VectorValue::Invalid,
+            }
+        })
+    }
+}
+
+/// 时间维度成员所代表时间段的第一天与最后一天。
+///
+/// 成员所在层级需要配置日期粒度，成员需要有开始日期，否则返回 `None`。
+fn member_date_span(olap_obj: MultiDimensionalEntity) -> Option<(OlapDate, OlapDate)> {
+    if let MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember { member, .. }) = olap_obj
+    {
+        let granularity = meta_cache::get_level_by_gid(member.level_gid).date_granularity?;
+        let start_date = member.start_date?;
+        return Some((
+            start_date.period_start(granularity),
+            start_date.period_end(granularity),
+        ));
+    }
+    None
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AstDateFnGetFirstDate {
+    Chain,
+    SegsObj(AstSegsObj),
+}
+
+impl ToVectorValue for AstDateFnGetFirstDate {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let param_olap_obj = match self {
+                AstDateFnGetFirstDate::SegsObj(segs) => segs.materialize(slice_tuple, context).await,
+                AstDateFnGetFirstDate::Chain => {
+                    if let Some(olap_obj) = outer_param {
+                        olap_obj
+                    } else {
+                        panic!("[dsuc-0-fff4] AstDateFnGetFirstDate::val()")
+                    }
+                }
+            };
+
+            match member_date_span(param_olap_obj) {
+                Some((first_date, _)) => VectorValue::Date(first_date),
+                None => VectorValue::Null,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AstDateFnGetLastDate {
+    Chain,
+    SegsObj(AstSegsObj),
+}
+
+impl ToVectorValue for AstDateFnGetLastDate {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            let param_olap_obj = match self {
+                AstDateFnGetLastDate::SegsObj(segs) => segs.materialize(slice_tuple, context).await,
+                AstDateFnGetLastDate::Chain => {
+                    if let Some(olap_obj) = outer_param {
+                        olap_obj
+                    } else {
+                        panic!("[dsuc-0-fff5] AstDateFnGetLastDate::val()")
+                    }
+                }
+            };
+
+            match member_date_span(param_olap_obj) {
+                Some((_, last_date)) => VectorValue::Date(last_date),
+                None => VectorValue::Null,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstNumFnJulianDate {
+    pub date_exp: AstExpression,
+}
+
+impl ToVectorValue for AstNumFnJulianDate {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match date_param(self.date_exp.val(slice_tuple, context, None).await) {
+                Ok(date) => VectorValue::Double(date.julian_day() as f64),
+                Err(cell_val) => cell_val,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstDateFnUnixDate {
+    pub julian_exp: AstExpression,
+}
+
+impl ToVectorValue for AstDateFnUnixDate {
+    fn val<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        _outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, VectorValue> {
+        Box::pin(async move {
+            match int_param(self.julian_exp.val(slice_tuple, context, None).await) {
+                Ok(julian_day) => VectorValue::Date(OlapDate::from_julian_day(julian_day)),
+                Err(cell_val) => cell_val,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AstStrFnProperties;
+    use crate::exmdx::ast::{AstExpression, AstFactory, AstTerm, ToVectorValue};
+    use crate::exmdx::mdd::TupleVector;
+    use crate::mdd::{
+        Cube, MemberRole, MultiDimensionalContext, MultiDimensionalEntity, VectorValue,
+    };
+    use crate::meta_cache::test_fixture::{calendar_snapshot, CUBE_GID, DIM_ROLE_GID, JAN_GID};
+    use crate::meta_cache::{self, with_snapshot};
+    use std::collections::HashMap;
+
+    async fn properties(factory: AstFactory, on_member: bool) -> VectorValue {
+        let props_fn = AstStrFnProperties {
+            name_exp: AstExpression {
+                terms: vec![(
+                    '+',
+                    AstTerm {
+                        factories: vec![('*', factory)],
+                    },
+                )],
+            },
+            typed: false,
+        };
+        with_snapshot(calendar_snapshot(), async {
+            let cube = Cube {
+                gid: CUBE_GID,
+                name: String::from("c"),
+            };
+            let mut context = MultiDimensionalContext::for_test(cube, HashMap::new(), 8);
+            let slice_tuple = TupleVector {
+                member_roles: vec![],
+            };
+            let outer_param = on_member.then(|| {
+                MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+                    dim_role: meta_cache::get_dimension_role_by_gid(DIM_ROLE_GID),
+                    member: meta_cache::get_member_by_gid(JAN_GID),
+                })
+            });
+            props_fn.val(&slice_tuple, &mut context, outer_param).await
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn properties_of_a_member() {
+        let name = properties(AstFactory::String(String::from("Name")), true).await;
+        assert_eq!(name, VectorValue::Str(String::from("Jan")));
+        let color = properties(AstFactory::String(String::from("Color")), true).await;
+        assert_eq!(color, VectorValue::Null);
+    }
+
+    #[tokio::test]
+    async fn properties_with_invalid_parameters_is_an_error() {
+        for (factory, on_member, code) in [
+            (AstFactory::Numeric(1.0), true, "[pr-002]"),
+            (AstFactory::String(String::from("Name")), false, "[pr-001]"),
+        ] {
+            match properties(factory, on_member).await {
+                VectorValue::Error(msg) => assert!(msg.starts_with(code), "{}", msg),
+                other => panic!("unexpected value {:?}", other),
+            }
+        }
+    }
+}
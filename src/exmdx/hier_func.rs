@@ -1,5 +1,11 @@
 use crate::exmdx::ast::AstExpression;
 use crate::exmdx::ast::AstSegsObj;
+use crate::exmdx::ast::{Materializable, ToVectorValue};
+use crate::exmdx::mdd::TupleVector;
+
+use crate::mdd::{DimensionRole, HierarchyRole, MemberRole, VectorValue};
+use crate::mdd::{MultiDimensionalContext, MultiDimensionalEntity};
+use crate::meta_cache;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstHierarchyFunction {
@@ -8,19 +14,229 @@ pub enum AstHierarchyFunction {
     Hierarchy(AstHierFnHierarchy),
 }
 
+impl AstHierarchyFunction {
+    /// Dimension 和 Dimensions 返回 DimensionRoleWrap，Hierarchy 返回 HierarchyRole。
+    pub async fn get_entity(
+        &self,
+        left_outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> MultiDimensionalEntity {
+        match self {
+            AstHierarchyFunction::Dimension(dim_fn) => {
+                let dim_role = dim_fn
+                    .do_get_dimension_role(left_outer_param, slice_tuple, context)
+                    .await;
+                MultiDimensionalEntity::DimensionRoleWrap(dim_role)
+            }
+            // 参数超出范围或找不到维度时返回错误单元格值，不中断整个查询
+            AstHierarchyFunction::Dimensions(dims_fn) => {
+                match dims_fn.do_get_dimension_role(slice_tuple, context).await {
+                    Ok(dim_role) => MultiDimensionalEntity::DimensionRoleWrap(dim_role),
+                    Err(msg) => MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)),
+                }
+            }
+            AstHierarchyFunction::Hierarchy(hier_fn) => {
+                let hier_role = hier_fn
+                    .do_get_hierarchy_role(left_outer_param, slice_tuple, context)
+                    .await;
+                MultiDimensionalEntity::HierarchyRole(hier_role)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstHierFnDimension {
     Chain,
     OlapObj(AstSegsObj),
 }
 
+impl AstHierFnDimension {
+    async fn do_get_dimension_role(
+        &self,
+        left_outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> DimensionRole {
+        let olap_obj = match self {
+            AstHierFnDimension::Chain => left_outer_param,
+            AstHierFnDimension::OlapObj(segs) => Some(segs.materialize(slice_tuple, context).await),
+        };
+
+        match olap_obj {
+            Some(olap_obj) => owner_dimension_role(olap_obj, "Dimension"),
+            None => panic!("[hf-101] Dimension requires a member, level or hierarchy parameter"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct AstHierFnDimensions {
     pub exp: AstExpression,
 }
 
+impl AstHierFnDimensions {
+    /// Dimensions(n) 按 Cube 中维度角色的顺序取第 n 个（从 0 开始），
+    /// Dimensions("name") 按名称查找维度角色。
+    async fn do_get_dimension_role(
+        &self,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> Result<DimensionRole, String> {
+        match self.exp.val(slice_tuple, context, None).await {
+            VectorValue::Double(idx) => {
+                let dim_roles = meta_cache::get_dimension_roles_by_cube_gid(context.cube.gid);
+                if idx < 0.0 || idx as usize >= dim_roles.len() {
+                    return Err(format!(
+                        "[hf-201] Dimensions({}) is out of range, the cube has {} dimensions",
+                        idx,
+                        dim_roles.len()
+                    ));
+                }
+                Ok(dim_roles[idx as usize].clone())
+            }
+            VectorValue::Str(name) => {
+                meta_cache::find_dimension_role_by_name(context.cube.gid, &name)
+                    .ok_or_else(|| format!("[hf-202] Dimension \"{}\" not found in the cube", name))
+            }
+            VectorValue::Error(msg) => Err(msg),
+            other => Err(format!(
+                "[hf-203] Dimensions() expects a number or a string, but got {:?}",
+                other
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstHierFnHierarchy {
     Chain,
     OlapObj(AstSegsObj),
 }
+
+impl AstHierFnHierarchy {
+    async fn do_get_hierarchy_role(
+        &self,
+        left_outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> HierarchyRole {
+        let olap_obj = match self {
+            AstHierFnHierarchy::Chain => left_outer_param,
+            AstHierFnHierarchy::OlapObj(segs) => Some(segs.materialize(slice_tuple, context).await),
+        };
+
+        match olap_obj {
+            // 基本成员和层级所在的层次
+            Some(MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+                dim_role,
+                member,
+            })) if !member.is_null() => {
+                let level = meta_cache::get_level_by_gid(member.level_gid);
+                HierarchyRole::new(dim_role, level.hierarchy_gid)
+            }
+            Some(MultiDimensionalEntity::LevelRole(lv_role)) => {
+                HierarchyRole::new(lv_role.dim_role, lv_role.level.hierarchy_gid)
+            }
+            Some(MultiDimensionalEntity::HierarchyRole(hier_role)) => hier_role,
+            // 计算成员、空成员和维度角色使用默认层次
+            Some(olap_obj) => {
+                let dim_role = owner_dimension_role(olap_obj, "Hierarchy");
+                let hierarchy_gid = dim_role.default_hierarchy_gid;
+                HierarchyRole::new(dim_role, hierarchy_gid)
+            }
+            None => panic!("[hf-301] Hierarchy requires a member, level or dimension parameter"),
+        }
+    }
+}
+
+/// 取得成员、层级、层次或维度角色所属的维度角色。
+fn owner_dimension_role(olap_obj: MultiDimensionalEntity, fn_name: &str) -> DimensionRole {
+    let dim_role_gid = match olap_obj {
+        MultiDimensionalEntity::DimensionRoleWrap(dim_role) => return dim_role,
+        MultiDimensionalEntity::HierarchyRole(hier_role) => return hier_role.dim_role,
+        MultiDimensionalEntity::LevelRole(lv_role) => return lv_role.dim_role,
+        MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember { dim_role, .. }) => {
+            return dim_role
+        }
+        MultiDimensionalEntity::MemberRoleWrap(MemberRole::FormulaMember {
+            dim_role_gid, ..
+        }) => dim_role_gid,
+        MultiDimensionalEntity::FormulaMemberWrap { dim_role_gid, .. } => dim_role_gid,
+        other => panic!(
+            "[hf-001] {}: the parameter is not a member, level, hierarchy or dimension: {:?}",
+            fn_name, other
+        ),
+    };
+
+    meta_cache::get_dimension_role_by_gid(dim_role_gid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AstHierFnDimensions, AstHierarchyFunction};
+    use crate::exmdx::ast::{AstExpression, AstFactory, AstTerm};
+    use crate::exmdx::mdd::TupleVector;
+    use crate::mdd::{Cube, MultiDimensionalContext, MultiDimensionalEntity, VectorValue};
+    use crate::meta_cache::test_fixture::{calendar_snapshot, CUBE_GID, DIM_ROLE_GID};
+    use crate::meta_cache::with_snapshot;
+    use std::collections::HashMap;
+
+    async fn dimensions(factory: AstFactory) -> MultiDimensionalEntity {
+        let dims_fn = AstHierarchyFunction::Dimensions(AstHierFnDimensions {
+            exp: AstExpression {
+                terms: vec![(
+                    '+',
+                    AstTerm {
+                        factories: vec![('*', factory)],
+                    },
+                )],
+            },
+        });
+        with_snapshot(calendar_snapshot(), async {
+            let cube = Cube {
+                gid: CUBE_GID,
+                name: String::from("c"),
+            };
+            let mut context = MultiDimensionalContext::for_test(cube, HashMap::new(), 8);
+            let slice_tuple = TupleVector {
+                member_roles: vec![],
+            };
+            dims_fn.get_entity(None, &slice_tuple, &mut context).await
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn dimensions_by_index_and_name() {
+        for factory in [
+            AstFactory::Numeric(0.0),
+            AstFactory::String(String::from("date")),
+        ] {
+            match dimensions(factory).await {
+                MultiDimensionalEntity::DimensionRoleWrap(dim_role) => {
+                    assert_eq!(dim_role.gid, DIM_ROLE_GID)
+                }
+                other => panic!("unexpected entity {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn dimensions_out_of_range_or_unknown_is_an_error() {
+        for (factory, code) in [
+            (AstFactory::Numeric(99.0), "[hf-201]"),
+            (AstFactory::Numeric(-1.0), "[hf-201]"),
+            (AstFactory::String(String::from("nope")), "[hf-202]"),
+            (AstFactory::Null, "[hf-203]"),
+        ] {
+            match dimensions(factory).await {
+                MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)) => {
+                    assert!(msg.starts_with(code), "{}", msg)
+                }
+                other => panic!("unexpected entity {:?}", other),
+            }
+        }
+    }
+}
@@ -5,10 +5,10 @@ use futures::future::BoxFuture;
 use crate::exmdx::ast::AstSegsObj;
 
 use crate::exmdx::ast::Materializable;
-use crate::exmdx::ast::{AstExpression, ToVectorValue};
+use crate::exmdx::ast::{AstExpression, AstFactory, ToVectorValue};
 use crate::exmdx::mdd::TupleVector;
 use crate::mdd::MultiDimensionalContext;
-use crate::mdd::{MemberRole, MultiDimensionalEntity};
+use crate::mdd::{MemberRole, MultiDimensionalEntity, VectorValue};
 
 pub trait ToBoolValue {
     fn bool_val<'a>(
@@ -18,9 +18,16 @@ pub trait ToBoolValue {
     ) -> BoxFuture<'a, bool>;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AstBoolOperator {
+    Or,
+    Xor,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct AstBoolExp {
-    pub terms: Vec<AstBoolTerm>,
+    // 第一项的运算符不参与计算
+    pub terms: Vec<(AstBoolOperator, AstBoolTerm)>,
 }
 
 impl ToBoolValue for AstBoolExp {
@@ -30,13 +37,25 @@ impl ToBoolValue for AstBoolExp {
         context: &'a mut MultiDimensionalContext,
     ) -> BoxFuture<'a, bool> {
         Box::pin(async move {
-            for bool_term in &self.terms {
-                let result = bool_term.bool_val(slice_tuple, context).await;
-                if result {
-                    return true;
+            let mut result = false;
+            for (index, (op, bool_term)) in self.terms.iter().enumerate() {
+                if index == 0 {
+                    result = bool_term.bool_val(slice_tuple, context).await;
+                    continue;
+                }
+
+                match op {
+                    AstBoolOperator::Or => {
+                        if !result {
+                            result = bool_term.bool_val(slice_tuple, context).await;
+                        }
+                    }
+                    AstBoolOperator::Xor => {
+                        result ^= bool_term.bool_val(slice_tuple, context).await
+                    }
                 }
             }
-            false
+            result
         })
     }
 }
@@ -98,6 +117,8 @@ impl ToBoolValue for AstBoolFactory {
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstBoolPrimary {
     ExpComparesExp(AstExpression, String, AstExpression),
+    ExpIsExp(AstExpression, AstExpression),
+    ExpIsEmpty(AstExpression),
     BoolExp(AstBoolExp),
     // BoolExp(Box<AstBoolExp>),
     BoolFn(AstBoolFunction),
@@ -116,6 +137,36 @@ impl ToBoolValue for AstBoolPrimary {
                     let val2 = exp2.val(slice_tuple, context, None).await;
                     val1.logical_cmp(op, &val2)
                 }
+                Self::ExpIsExp(exp1, exp2) => {
+                    match (exp1.as_olap_object(), exp2.as_olap_object()) {
+                        (Some(obj1), Some(obj2)) => {
+                            let olap_obj1 =
+                                materialize_olap_object(obj1, slice_tuple, context).await;
+                            let olap_obj2 =
+                                materialize_olap_object(obj2, slice_tuple, context).await;
+                            is_same_olap_object(olap_obj1, olap_obj2)
+                        }
+                        // Member_Expression IS NULL 判断是否为空成员
+                        (Some(obj), None) if exp2.is_null_literal() => is_null_olap_object(
+                            materialize_olap_object(obj, slice_tuple, context).await,
+                        ),
+                        (None, Some(obj)) if exp1.is_null_literal() => is_null_olap_object(
+                            materialize_olap_object(obj, slice_tuple, context).await,
+                        ),
+                        _ => {
+                            let val1 = exp1.val(slice_tuple, context, None).await;
+                            let val2 = exp2.val(slice_tuple, context, None).await;
+                            match (&val1, &val2) {
+                                (VectorValue::Null, VectorValue::Null) => true,
+                                (VectorValue::Null, _) | (_, VectorValue::Null) => false,
+                                _ => val1.logical_cmp(&String::from("="), &val2),
+                            }
+                        }
+                    }
+                }
+                Self::ExpIsEmpty(exp) => {
+                    exp.val(slice_tuple, context, None).await == VectorValue::Null
+                }
                 Self::BoolExp(bool_exp) => bool_exp.bool_val(slice_tuple, context).await,
                 Self::BoolFn(bool_fn) => bool_fn.bool_val(slice_tuple, context).await,
             }
@@ -123,6 +174,87 @@ impl ToBoolValue for AstBoolPrimary {
     }
 }
 
+async fn materialize_olap_object(
+    factory: &AstFactory,
+    slice_tuple: &TupleVector,
+    context: &mut MultiDimensionalContext,
+) -> MultiDimensionalEntity {
+    match factory {
+        AstFactory::AstSegsObj(segs) => segs.materialize(slice_tuple, context).await,
+        AstFactory::AstTuple(tuple) => tuple.materialize(slice_tuple, context).await,
+        _ => panic!(
+            "[is-001] IS expects a member or tuple operand, but got {:?}",
+            factory
+        ),
+    }
+}
+
+/// IS 运算中成员按单成员 Tuple 处理。
+fn olap_object_member_roles(olap_obj: MultiDimensionalEntity) -> Vec<MemberRole> {
+    match olap_obj {
+        MultiDimensionalEntity::MemberRoleWrap(member_role) => vec![member_role],
+        MultiDimensionalEntity::FormulaMemberWrap {
+            dim_role_gid,
+            frml_gid,
+            exp,
+        } => {
+            vec![MemberRole::FormulaMember {
+                dim_role_gid,
+                frml_gid,
+                exp,
+            }]
+        }
+        MultiDimensionalEntity::TupleWrap(tuple) => tuple.member_roles,
+        other => panic!(
+            "[is-002] IS expects a member or tuple operand, but got {:?}",
+            other
+        ),
+    }
+}
+
+fn is_same_member_role(mr1: &MemberRole, mr2: &MemberRole) -> bool {
+    match (mr1, mr2) {
+        (
+            MemberRole::BaseMember {
+                dim_role: dim_role1,
+                member: member1,
+            },
+            MemberRole::BaseMember {
+                dim_role: dim_role2,
+                member: member2,
+            },
+        ) => dim_role1.gid == dim_role2.gid && member1.gid == member2.gid,
+        (MemberRole::FormulaMember { .. }, MemberRole::FormulaMember { .. }) => mr1 == mr2,
+        _ => false,
+    }
+}
+
+/// 两个成员或 Tuple 包含相同的成员时相同，与成员的顺序无关。
+fn is_same_olap_object(
+    olap_obj1: MultiDimensionalEntity,
+    olap_obj2: MultiDimensionalEntity,
+) -> bool {
+    let member_roles1 = olap_object_member_roles(olap_obj1);
+    let member_roles2 = olap_object_member_roles(olap_obj2);
+    member_roles1.len() == member_roles2.len()
+        && member_roles1.iter().all(|mr1| {
+            member_roles2
+                .iter()
+                .any(|mr2| is_same_member_role(mr1, mr2))
+        })
+}
+
+fn is_null_olap_object(olap_obj: MultiDimensionalEntity) -> bool {
+    match olap_obj {
+        MultiDimensionalEntity::Nothing => true,
+        MultiDimensionalEntity::MemberRoleWrap(member_role) => member_role.is_null_member(),
+        MultiDimensionalEntity::TupleWrap(tuple) => {
+            tuple.member_roles.iter().any(|mr| mr.is_null_member())
+        }
+        _ => false,
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstBoolFunction {
     IsLeaf(AstBoolFnIsLeaf),
@@ -215,11 +347,11 @@ pub struct AstBoolFnIsEmpty {
 impl ToBoolValue for AstBoolFnIsEmpty {
     fn bool_val<'a>(
         &'a self,
-        _slice_tuple: &'a TupleVector,
-        _context: &'a mut MultiDimensionalContext,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
     ) -> BoxFuture<'a, bool> {
         Box::pin(async move {
-            false
+            self.val_exp.val(slice_tuple, context, None).await == VectorValue::Null
         })
     }
 }
@@ -108,6 +108,9 @@ impl AstLevelFnLevels {
         if let Some(MultiDimensionalEntity::DimensionRoleWrap(dr)) = left_outer_param {
             def_hierarchy_gid = dr.default_hierarchy_gid;
             param_dim_role = Some(dr);
+        } else if let Some(MultiDimensionalEntity::HierarchyRole(hier_role)) = left_outer_param {
+            def_hierarchy_gid = hier_role.hierarchy_gid;
+            param_dim_role = Some(hier_role.dim_role);
         } else if let Self::SegsObj_Exp(segs_obj, _) = self {
             if let MultiDimensionalEntity::DimensionRoleWrap(dr) =
                 segs_obj.materialize(slice_tuple, context).await
@@ -1,4 +1,4 @@
-use crate::mdd::MemberRole;
+use crate::mdd::{MemberRole, MultiDimensionalContext};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TupleVector {
@@ -22,6 +22,16 @@ impl std::fmt::Display for TupleVector {
 }
 
 impl TupleVector {
+    /// 生成可以被 StrToTuple 重新解析的字符串，形如 `(m1, m2)`。
+    pub fn to_mdx_str(&self, context: &MultiDimensionalContext) -> String {
+        let member_names: Vec<String> = self
+            .member_roles
+            .iter()
+            .map(|mr| mr.unique_name(context))
+            .collect();
+        format!("({})", member_names.join(", "))
+    }
+
     /*
      * self:   [Goods], [Transport], [starting region], [ending region], [starting date], [completion date], [**MeasureDimRole**]
      * other:  [Transport], [completion date], [Goods], [starting region], [ending region]
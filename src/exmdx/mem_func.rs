@@ -4,13 +4,17 @@ use core::panic;
 
 use crate::exmdx::ast::AstExpression;
 use crate::exmdx::ast::AstSegsObj;
+use crate::exmdx::ast::AstSet;
 
 use crate::exmdx::ast::{Materializable, ToVectorValue};
 use crate::exmdx::mdd::TupleVector;
 use crate::mdd::MultiDimensionalContext;
-use crate::mdd::{MemberRole, MultiDimensionalEntity};
+use crate::mdd::{DimensionRole, Member, MemberRole, MultiDimensionalEntity};
 use crate::meta_cache;
 
+use crate::calendar::OlapDate;
+use crate::mdd::VectorValue;
+
 // declare submodule for specific member function implementations
 pub mod first_child;
 // re-export so generated parser and other modules can refer to `mem_func::AstMemberFnFirstChild`
@@ -49,6 +53,9 @@ pub enum AstMemberFunction {
     Ancestor(AstMemberFnAncestor),
     Cousin(AstMemberFnCousin),
     DefaultMember(AstMemberFnDefaultMember),
+    StrToMember(AstMemberFnStrToMember),
+    DateToMember(AstMemberFnDateToMember),
+    DataMember(AstMemberFnDataMember),
 }
 
 impl AstMemberFunction {
@@ -250,6 +257,19 @@ impl AstMemberFunction {
                     .resolve_member_role(slice_tuple, context, left_outer_param)
                     .await,
             ),
+            Self::StrToMember(str_to_member) => {
+                str_to_member.do_get_member(slice_tuple, context).await
+            }
+            Self::DateToMember(member_role_fn) => MultiDimensionalEntity::MemberRoleWrap(
+                member_role_fn
+                    .resolve_member_role(slice_tuple, context, left_outer_param)
+                    .await,
+            ),
+            Self::DataMember(member_role_fn) => MultiDimensionalEntity::MemberRoleWrap(
+                member_role_fn
+                    .resolve_member_role(slice_tuple, context, left_outer_param)
+                    .await,
+            ),
         }
     }
 }
@@ -270,22 +290,15 @@ impl AstMemberFnClosingPeriod {
         slice_tuple: &TupleVector,
         context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
-        match (left_outer_param, level_param, member_param) {
-            (None, Some(lv_segs), None) => {
-                let olap_obj = lv_segs.materialize(slice_tuple, context).await;
-                if let MultiDimensionalEntity::LevelRole(lv_role) = olap_obj {
-                    MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
-                        dim_role: lv_role.dim_role.clone(),
-                        member: meta_cache::get_member_by_gid(lv_role.level.closing_period_gid),
-                    })
-                } else {
-                    panic!("[850BHE] The entity is not a LevelRole variant.");
-                }
-            }
-            _ => {
-                panic!("Invalid parameter combination. Only level_param should be Some, and left_outer_param and member_param should be None.");
-            }
-        }
+        period_edge_member(
+            left_outer_param,
+            level_param,
+            member_param,
+            slice_tuple,
+            context,
+            true,
+        )
+        .await
     }
 }
 
@@ -305,22 +318,109 @@ impl AstMemberFnOpeningPeriod {
         slice_tuple: &TupleVector,
         context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
-        match (left_outer_param, level_param, member_param) {
-            (None, Some(lv_segs), None) => {
-                let olap_obj = lv_segs.materialize(slice_tuple, context).await;
-                if let MultiDimensionalEntity::LevelRole(lv_role) = olap_obj {
-                    MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
-                        dim_role: lv_role.dim_role.clone(),
-                        member: meta_cache::get_member_by_gid(lv_role.level.opening_period_gid),
-                    })
-                } else {
-                    panic!("[833BHE] The entity is not a LevelRole variant.");
-                }
+        period_edge_member(
+            left_outer_param,
+            level_param,
+            member_param,
+            slice_tuple,
+            context,
+            false,
+        )
+        .await
+    }
+}
+
+/// OpeningPeriod / ClosingPeriod 的共同实现，`last` 为 true 时取最后一个后代（ClosingPeriod）。
+///
+/// - 只有 Level 参数时，优先使用 Level 上配置的 opening_period_gid / closing_period_gid；
+/// - Member 默认为 Level 所在维度的当前成员，Level 默认为 Member 的下一层级；
+/// - 不存在对应的后代（Level 在 Member 之上、Member 是叶子或计算成员）时返回空成员。
+async fn period_edge_member(
+    left_outer_param: Option<MultiDimensionalEntity>,
+    level_param: Option<&AstSegsObj>,
+    member_param: Option<&AstSegsObj>,
+    slice_tuple: &TupleVector,
+    context: &mut MultiDimensionalContext,
+    last: bool,
+) -> MultiDimensionalEntity {
+    let fn_name = if last { "ClosingPeriod" } else { "OpeningPeriod" };
+
+    let lv_role = match level_param {
+        Some(level_segs) => match level_segs.materialize(slice_tuple, context).await {
+            MultiDimensionalEntity::LevelRole(lv_role) => Some(lv_role),
+            _ if last => panic!("[850BHE] The entity is not a LevelRole variant."),
+            _ => panic!("[833BHE] The entity is not a LevelRole variant."),
+        },
+        None => None,
+    };
+
+    if let (Some(lv_role), None, None) = (&lv_role, member_param, &left_outer_param) {
+        let period_gid = if last {
+            lv_role.level.closing_period_gid
+        } else {
+            lv_role.level.opening_period_gid
+        };
+        if period_gid != 0 {
+            return MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+                dim_role: lv_role.dim_role.clone(),
+                member: meta_cache::get_member_by_gid(period_gid),
+            });
+        }
+    }
+
+    let member_role = match (member_param, left_outer_param, &lv_role) {
+        (Some(member_segs), _, _) => match member_segs.materialize(slice_tuple, context).await {
+            MultiDimensionalEntity::MemberRoleWrap(mr) => mr,
+            _ => panic!("[hsb778] {}: member parameter is not a MemberRole", fn_name),
+        },
+        (None, Some(MultiDimensionalEntity::MemberRoleWrap(mr)), _) => mr,
+        (None, None, Some(lv_role)) => current_member_role(&lv_role.dim_role, slice_tuple, fn_name),
+        _ => panic!(
+            "[hsb778] {} requires a level, a member or an outer member parameter",
+            fn_name
+        ),
+    };
+
+    let (dim_role, member) = match member_role {
+        MemberRole::BaseMember { dim_role, member } => (dim_role, member),
+        MemberRole::FormulaMember { dim_role_gid, .. } => {
+            return null_member_of(dim_role_gid);
+        }
+    };
+
+    if member.is_null() {
+        return MultiDimensionalEntity::MemberRoleWrap(MemberRole::null_member(dim_role));
+    }
+
+    let level_gid = match lv_role {
+        Some(lv_role) => {
+            if lv_role.dim_role.gid != dim_role.gid {
+                panic!(
+                    "[hsb779] {}: member and level belong to different DimensionRole ({} vs {})",
+                    fn_name, dim_role.gid, lv_role.dim_role.gid
+                );
             }
-            _ => {
-                panic!("[hsb778] Invalid parameter combination. Only level_param should be Some, and left_outer_param and member_param should be None.");
+            lv_role.level.gid
+        }
+        None => {
+            let children = meta_cache::get_ordered_children(member.gid);
+            match children.first() {
+                Some(child) => child.level_gid,
+                None => {
+                    return MultiDimensionalEntity::MemberRoleWrap(MemberRole::null_member(
+                        dim_role,
+                    ))
+                }
             }
         }
+    };
+
+    match meta_cache::find_edge_descendant_on_level(member.gid, level_gid, last) {
+        Some(period_member) => MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+            dim_role,
+            member: period_member,
+        }),
+        None => MultiDimensionalEntity::MemberRoleWrap(MemberRole::null_member(dim_role)),
     }
 }
 
@@ -350,6 +450,14 @@ impl AstMemberFnCurrentMember {
             }
         }
 
+        let param = match param {
+            // 层次上的当前成员即其维度角色的当前成员
+            MultiDimensionalEntity::HierarchyRole(hier_role) => {
+                MultiDimensionalEntity::DimensionRoleWrap(hier_role.dim_role)
+            }
+            param => param,
+        };
+
         match param {
             MultiDimensionalEntity::DimensionRoleWrap(param_dim_role) => {
                 for mr in slice_tuple.member_roles.iter() {
@@ -383,7 +491,7 @@ pub enum AstMemberFnParent {
 impl AstMemberFnParent {
     async fn do_get_member(
         left_unique_param: Option<MultiDimensionalEntity>,
-        context: &mut MultiDimensionalContext,
+        _context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
         if let MultiDimensionalEntity::MemberRoleWrap(mr) = left_unique_param.unwrap() {
             if let MemberRole::BaseMember { dim_role, member } = mr {
@@ -393,19 +501,10 @@ impl AstMemberFnParent {
                         member,
                     });
                 } else {
-                    let obj = context
-                        .grpc_client
-                        .get_universal_olap_entity_by_gid(member.parent_gid)
-                        .await
-                        .unwrap();
-                    if let MultiDimensionalEntity::MemberWrap(member) = obj {
-                        return MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
-                            dim_role,
-                            member,
-                        });
-                    } else {
-                        todo!()
-                    }
+                    return MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+                        dim_role,
+                        member: meta_cache::get_member_by_gid(member.parent_gid),
+                    });
                 }
             }
         }
@@ -431,11 +530,28 @@ pub enum AstMemberFnLag {
 impl MemberRoleAccess for AstMemberFnLag {
     fn resolve_member_role<'a>(
         &'a self,
-        _slice_tuple: &'a TupleVector,
-        _context: &'a mut MultiDimensionalContext,
-        _outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
     ) -> BoxFuture<'a, MemberRole> {
-        Box::pin(async move { todo!() })
+        Box::pin(async move {
+            let (member_segs, idx_exp) = match self {
+                AstMemberFnLag::Chain_IndexExp(idx_exp) => (None, idx_exp),
+                AstMemberFnLag::MemberSegs_IndexExp(member_segs, idx_exp) => {
+                    (Some(member_segs), idx_exp)
+                }
+            };
+
+            let (dim_role, member) =
+                base_member_param(member_segs, outer_param, slice_tuple, context, "Lag").await;
+            let index = index_param(idx_exp, slice_tuple, context, "Lag").await;
+
+            // Lag(n) 向前移动 n 个位置，n 为负数时向后移动，超出层级两端时返回空成员
+            match meta_cache::get_member_on_level_by_offset(member.gid, -index) {
+                Some(member) => MemberRole::BaseMember { dim_role, member },
+                None => MemberRole::null_member(dim_role),
+            }
+        })
     }
 }
 
@@ -458,11 +574,28 @@ pub enum AstMemberFnLead {
 impl MemberRoleAccess for AstMemberFnLead {
     fn resolve_member_role<'a>(
         &'a self,
-        _slice_tuple: &'a TupleVector,
-        _context: &'a mut MultiDimensionalContext,
-        _outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
     ) -> BoxFuture<'a, MemberRole> {
-        Box::pin(async move { todo!() })
+        Box::pin(async move {
+            let (member_segs, idx_exp) = match self {
+                AstMemberFnLead::Chain_IndexExp(idx_exp) => (None, idx_exp),
+                AstMemberFnLead::MemberSegs_IndexExp(member_segs, idx_exp) => {
+                    (Some(member_segs), idx_exp)
+                }
+            };
+
+            let (dim_role, member) =
+                base_member_param(member_segs, outer_param, slice_tuple, context, "Lead").await;
+            let index = index_param(idx_exp, slice_tuple, context, "Lead").await;
+
+            // Lead(n) 向后移动 n 个位置，n 为负数时向前移动，超出层级两端时返回空成员
+            match meta_cache::get_member_on_level_by_offset(member.gid, index) {
+                Some(member) => MemberRole::BaseMember { dim_role, member },
+                None => MemberRole::null_member(dim_role),
+            }
+        })
     }
 }
 
@@ -476,6 +609,12 @@ pub enum AstMemberFnParallelPeriod {
 }
 
 impl AstMemberFnParallelPeriod {
+    /// ParallelPeriod( [ Level_Expression [ ,Index [ , Member_Expression ] ] ] )
+    ///
+    /// 等价于 Cousin(Member, Ancestor(Member, Level).Lag(Index))：
+    /// - Member 默认为 Level 所在维度的当前成员，Level 默认为 Member 的上一层级，Index 默认为 1；
+    /// - 祖先平移可以跨越父节点，子成员数量不足时取最后一个子成员（例如 3 月 31 日对应 2 月 28/29 日）；
+    /// - 不存在平行期间（越界、Level 比 Member 更深、计算成员）时返回空成员。
     async fn do_get_member(
         left_outer_param: Option<MultiDimensionalEntity>,
         level_param: Option<&AstSegsObj>,
@@ -484,166 +623,80 @@ impl AstMemberFnParallelPeriod {
         slice_tuple: &TupleVector,
         context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
+        let lv_role = match level_param {
+            Some(level_segs) => match level_segs.materialize(slice_tuple, context).await {
+                MultiDimensionalEntity::LevelRole(lv_role) => Some(lv_role),
+                _ => panic!("[pp-101] level_param did not materialize to LevelRole"),
+            },
+            None => None,
+        };
 
-        /*
-         Resolve member priority: 1) if `member_param` is present, materialize it
-         and use its `MemberRole`; 2) otherwise use `left_outer_param` if it
-         is a `MemberRole`; otherwise panic. After resolution print debug info;
-         the actual ParallelPeriod computation is not implemented yet.
-        */
-        let member_role: Option<MemberRole> = if let Some(member_segs) = member_param {
+        // member_param > left_outer_param > Level 所在维度的当前成员
+        let member_role: MemberRole = if let Some(member_segs) = member_param {
             match member_segs.materialize(slice_tuple, context).await {
-                MultiDimensionalEntity::MemberRoleWrap(mr) => Some(mr),
-                _ => None,
+                MultiDimensionalEntity::MemberRoleWrap(mr) => mr,
+                _ => panic!("[pp-003] ParallelPeriod requires a member (member_param) or left_outer_param that resolves to a MemberRole"),
             }
         } else if let Some(outer_ins_param) = left_outer_param {
             match outer_ins_param {
-                MultiDimensionalEntity::MemberRoleWrap(mr) => Some(mr),
-                _ => None,
+                MultiDimensionalEntity::MemberRoleWrap(mr) => mr,
+                _ => panic!("[pp-003] ParallelPeriod requires a member (member_param) or left_outer_param that resolves to a MemberRole"),
             }
+        } else if let Some(lv_role) = &lv_role {
+            current_member_role(&lv_role.dim_role, slice_tuple, "ParallelPeriod")
         } else {
-            None
+            panic!("[pp-003] ParallelPeriod requires a member (member_param) or left_outer_param that resolves to a MemberRole");
         };
 
-        if member_role.is_none() {
-            panic!("[pp-003] ParallelPeriod requires a member (member_param) or left_outer_param that resolves to a MemberRole");
+        let offset: i64 = match idx_param {
+            Some(idx_exp) => index_param(idx_exp, slice_tuple, context, "ParallelPeriod").await,
+            None => 1,
+        };
+        if offset < i64::from(i32::MIN) || offset > i64::from(i32::MAX) {
+            panic!("[pp-500] offset out of i32 range: {}", offset);
         }
 
-        let member_role = member_role.unwrap();
-
-
-        // Resolve the LevelRole to use for ParallelPeriod:
-        // 1) If `level_param` is provided, materialize it and require a LevelRole.
-        // 2) Otherwise derive the LevelRole from `member_role` (must be BaseMember).
-        // Panic with clear messages if neither path yields a LevelRole.
-        let lv_role = if let Some(level_segs) = level_param {
-            let olap_obj = level_segs.materialize(slice_tuple, context).await;
-            if let MultiDimensionalEntity::LevelRole(lv_role) = olap_obj {
-                lv_role
-            } else {
-                panic!("[pp-101] level_param did not materialize to LevelRole");
-            }
-        } else {
-            match &member_role {
-                MemberRole::BaseMember { dim_role, member } => {
-                    // If possible, derive the target Level from the member's parent
-                    // (the "upper" level). If the member has no parent or parent
-                    // can't be fetched, fall back to the member's own level.
-                    let level = if member.parent_gid != 0 {
-                        let parent_obj = context
-                            .grpc_client
-                            .get_universal_olap_entity_by_gid(member.parent_gid)
-                            .await
-                            .ok();
-
-                        if let Some(MultiDimensionalEntity::MemberWrap(parent_member)) = parent_obj {
-                            meta_cache::get_level_by_gid(parent_member.level_gid)
-                        } else {
-                            // Fallback to member's level
-                            meta_cache::get_level_by_gid(member.level_gid)
-                        }
-                    } else {
-                        meta_cache::get_level_by_gid(member.level_gid)
-                    };
-
-                    crate::mdd::LevelRole::new(dim_role.clone(), level)
-                }
-                MemberRole::FormulaMember { .. } => {
-                    panic!("[pp-102] Cannot derive LevelRole from a FormulaMember");
-                }
+        let (dim_role, member) = match member_role {
+            MemberRole::BaseMember { dim_role, member } => (dim_role, member),
+            // 计算成员没有平行期间
+            MemberRole::FormulaMember { dim_role_gid, .. } => {
+                return null_member_of(dim_role_gid);
             }
         };
 
+        if member.is_null() {
+            return MultiDimensionalEntity::MemberRoleWrap(MemberRole::null_member(dim_role));
+        }
 
-        // Determine parallel offset: evaluate `idx_param` to an integer, default to 1.
-        let offset: i64 = if let Some(idx_exp) = idx_param {
-            let val = idx_exp.val(slice_tuple, context, None).await;
-            match val {
-                crate::mdd::VectorValue::Double(n) => n as i64,
-                crate::mdd::VectorValue::Str(s) => s.parse::<i64>().unwrap_or_else(|_| {
-                    panic!("[pp-201] idx_param string could not be parsed as integer: {}", s)
-                }),
-                crate::mdd::VectorValue::Null | crate::mdd::VectorValue::Invalid => {
-                    panic!("[pp-202] idx_param evaluated to Null/Invalid")
-                }
-            }
-        } else {
-            1
-        };
-
-        // Ensure the resolved member and the target level belong to the same DimensionRole.
-        // If not, panic with a clear message. Also print the target level and the
-        // source member in separate, concise lines for debugging. Use the cached
-        // helper `meta_cache::get_member_ancestor_on_level` to obtain the ancestor.
-        let ancestor_member = match &member_role {
-            MemberRole::BaseMember { dim_role, member } => {
-                if dim_role.gid != lv_role.dim_role.gid {
+        let level = match lv_role {
+            Some(lv_role) => {
+                if lv_role.dim_role.gid != dim_role.gid {
                     panic!("[pp-400] ParallelPeriod: member and level belong to different DimensionRole (member.dim_role_gid={} vs level.dim_role_gid={})", dim_role.gid, lv_role.dim_role.gid);
                 }
-
-                if lv_role.level.level > member.level {
-                    panic!("[pp-300] ParallelPeriod: target level is deeper than member's level (not supported yet)");
-                }
-
-                println!("ParallelPeriod: target level = {}", lv_role.level.level);
-                println!("ParallelPeriod: source member gid = {}, member level = {}", member.gid, member.level);
-
-                // Use cache-based traversal to get the ancestor at the target level.
-                meta_cache::get_member_ancestor_on_level(member.gid, lv_role.level.gid)
+                lv_role.level
             }
-            MemberRole::FormulaMember { .. } => {
-                panic!("[pp-401] ParallelPeriod: member is a FormulaMember (unsupported)");
+            // 默认使用上一层级，根成员没有上一层级时使用其自身层级
+            None if member.parent_gid != 0 => {
+                let parent = meta_cache::get_member_by_gid(member.parent_gid);
+                meta_cache::get_level_by_gid(parent.level_gid)
             }
+            None => meta_cache::get_level_by_gid(member.level_gid),
         };
 
+        if level.level > member.level {
+            return MultiDimensionalEntity::MemberRoleWrap(MemberRole::null_member(dim_role));
+        }
 
-        // 找到平行的member：使用 caching helper 或 gRPC 路径（按需）
-        // Basic ParallelPeriod implementation:
-        // - require a BaseMember as the source member
-        // - determine the target level (from param or from the member)
-        // - climb the member's parent chain until we reach the target level
-        // - apply `offset` as a positional shift among siblings of that ancestor
-        // Note: This implements a pragmatic, consistent behaviour; full MDX
-        // semantics (e.g. when target level is deeper than source) may need
-        // further refinement.
-
-        match member_role {
-            MemberRole::BaseMember { dim_role, member: source_member } => {
-                // Use the ancestor obtained from cache above.
-                let cur_member = ancestor_member.clone();
-
-                // If offset == 0 simply return the ancestor at target level.
-                if offset == 0 {
-                    return MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
-                        dim_role: dim_role.clone(),
-                        member: meta_cache::get_member_by_gid(cur_member.gid),
-                    });
-                }
-
-                // Prefer the gRPC-based helper which shifts ancestor and finds
-                // the corresponding descendant by path indices. Convert offset
-                // to i32 with bounds check.
-                if offset < i64::from(i32::MIN) || offset > i64::from(i32::MAX) {
-                    panic!("[pp-500] offset out of i32 range: {}", offset);
-                }
-                let offset_i32 = offset as i32;
-
-                let shifted = meta_cache::shift_ancestor_and_find_member(
-                    &mut context.grpc_client,
-                    cur_member.gid,
-                    source_member.gid,
-                    offset_i32,
-                )
-                .await;
+        let ancestor = meta_cache::get_member_ancestor_on_level(member.gid, level.gid);
+        let shifted =
+            meta_cache::shift_ancestor_and_find_member(ancestor.gid, member.gid, offset as i32);
 
-                return MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
-                    dim_role: dim_role.clone(),
-                    member: meta_cache::get_member_by_gid(shifted.gid),
-                });
-            }
-            MemberRole::FormulaMember { .. } => {
-                panic!("[pp-306] ParallelPeriod not supported for FormulaMember");
-            }
+        match shifted {
+            Some(shifted) => MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+                dim_role,
+                member: shifted,
+            }),
+            None => MultiDimensionalEntity::MemberRoleWrap(MemberRole::null_member(dim_role)),
         }
     }
 }
@@ -747,11 +800,62 @@ pub enum AstMemberFnAncestor {
 impl MemberRoleAccess for AstMemberFnAncestor {
     fn resolve_member_role<'a>(
         &'a self,
-        _slice_tuple: &'a TupleVector,
-        _context: &'a mut MultiDimensionalContext,
-        _outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
     ) -> BoxFuture<'a, MemberRole> {
-        Box::pin(async move { todo!() })
+        Box::pin(async move {
+            let member_segs = match self {
+                AstMemberFnAncestor::Chain_LevelSegs(_)
+                | AstMemberFnAncestor::Chain_Distance(_) => None,
+                AstMemberFnAncestor::MemberSegs_LevelSegs(member_segs, _)
+                | AstMemberFnAncestor::MemberSegs_Distance(member_segs, _) => Some(member_segs),
+            };
+
+            let (dim_role, member) =
+                base_member_param(member_segs, outer_param, slice_tuple, context, "Ancestor").await;
+
+            // 空成员、不在同一层次或不在其上级的层级、超出顶层的距离都没有祖先，返回空成员
+            let ancestor = match self {
+                AstMemberFnAncestor::Chain_LevelSegs(level_segs)
+                | AstMemberFnAncestor::MemberSegs_LevelSegs(_, level_segs) => {
+                    let lv_role = match level_segs.materialize(slice_tuple, context).await {
+                        MultiDimensionalEntity::LevelRole(lv_role) => lv_role,
+                        _ => panic!("[anc-001] Ancestor(): level parameter did not materialize to LevelRole"),
+                    };
+                    if member.is_null()
+                        || lv_role.level.hierarchy_gid
+                            != meta_cache::get_level_by_gid(member.level_gid).hierarchy_gid
+                        || lv_role.level.level > member.level
+                    {
+                        None
+                    } else {
+                        Some(meta_cache::get_member_ancestor_on_level(
+                            member.gid,
+                            lv_role.level.gid,
+                        ))
+                    }
+                }
+                AstMemberFnAncestor::Chain_Distance(distance)
+                | AstMemberFnAncestor::MemberSegs_Distance(_, distance) => {
+                    // 距离为向上的层数，负数写法（例如 -1）与正数等价
+                    let distance = u32::try_from(distance.unsigned_abs()).unwrap_or(u32::MAX);
+                    if member.is_null() {
+                        None
+                    } else {
+                        meta_cache::get_member_ancestor_by_distance(member.gid, distance)
+                    }
+                }
+            };
+
+            match ancestor {
+                Some(ancestor) => MemberRole::BaseMember {
+                    dim_role,
+                    member: ancestor,
+                },
+                None => MemberRole::null_member(dim_role),
+            }
+        })
     }
 }
 
@@ -765,14 +869,123 @@ pub enum AstMemberFnCousin {
 impl MemberRoleAccess for AstMemberFnCousin {
     fn resolve_member_role<'a>(
         &'a self,
-        _slice_tuple: &'a TupleVector,
-        _context: &'a mut MultiDimensionalContext,
-        _outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
     ) -> BoxFuture<'a, MemberRole> {
-        Box::pin(async move { todo!() })
+        Box::pin(async move {
+            let (member_segs, anc_member_segs) = match self {
+                AstMemberFnCousin::Chain_AncestorMemberSegs(anc_member_segs) => {
+                    (None, anc_member_segs)
+                }
+                AstMemberFnCousin::MemberSegs_AncestorMemberSegs(member_segs, anc_member_segs) => {
+                    (Some(member_segs), anc_member_segs)
+                }
+            };
+
+            let (dim_role, member) =
+                base_member_param(member_segs, outer_param, slice_tuple, context, "Cousin").await;
+            let (_, anc_member) =
+                base_member_param(Some(anc_member_segs), None, slice_tuple, context, "Cousin")
+                    .await;
+
+            // 空成员、不在同一层次或比 member 更深的祖先成员下没有表亲，
+            // 祖先成员下没有相同位置的成员（例如 Cousin([Jan].[31], [Feb])）时同样返回空成员
+            if member.is_null()
+                || anc_member.is_null()
+                || anc_member.level > member.level
+                || meta_cache::get_level_by_gid(anc_member.level_gid).hierarchy_gid
+                    != meta_cache::get_level_by_gid(member.level_gid).hierarchy_gid
+            {
+                return MemberRole::null_member(dim_role);
+            }
+
+            // member 在其同层祖先下的位置，映射到 anc_member 下的相同位置
+            let own_ancestor =
+                meta_cache::get_member_ancestor_on_level(member.gid, anc_member.level_gid);
+            let position = meta_cache::member_position_under_ancestor(own_ancestor.gid, member.gid);
+
+            match meta_cache::find_member_by_position(anc_member.gid, &position) {
+                Some(cousin) => MemberRole::BaseMember {
+                    dim_role,
+                    member: cousin,
+                },
+                None => MemberRole::null_member(dim_role),
+            }
+        })
+    }
+}
+
+/// 解析成员参数：优先使用函数内的成员表达式，否则使用链式调用的左侧成员。只支持基本成员。
+async fn base_member_param(
+    member_segs: Option<&AstSegsObj>,
+    outer_param: Option<MultiDimensionalEntity>,
+    slice_tuple: &TupleVector,
+    context: &mut MultiDimensionalContext,
+    fn_name: &str,
+) -> (DimensionRole, Member) {
+    let olap_obj = match member_segs {
+        Some(member_segs) => Some(member_segs.materialize(slice_tuple, context).await),
+        None => outer_param,
+    };
+
+    match olap_obj {
+        Some(MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+            dim_role,
+            member,
+        })) => (dim_role, member),
+        Some(MultiDimensionalEntity::MemberRoleWrap(MemberRole::FormulaMember { .. }))
+        | Some(MultiDimensionalEntity::FormulaMemberWrap { .. }) => {
+            panic!("[mf-001] {} not supported for FormulaMember", fn_name)
+        }
+        _ => panic!(
+            "[mf-002] {} requires a member parameter or outer_param that resolves to a MemberRole",
+            fn_name
+        ),
     }
 }
 
+/// 计算位置参数（Lag/Lead 的 Index），必须是数值。
+async fn index_param(
+    idx_exp: &AstExpression,
+    slice_tuple: &TupleVector,
+    context: &mut MultiDimensionalContext,
+    fn_name: &str,
+) -> i64 {
+    match idx_exp.val(slice_tuple, context, None).await {
+        VectorValue::Double(idx) => idx as i64,
+        other => panic!(
+            "[mf-003] {}: index must be numeric, but got {:?}",
+            fn_name, other
+        ),
+    }
+}
+
+/// 取得 slice_tuple 中指定维度角色的当前成员。
+fn current_member_role(
+    dim_role: &DimensionRole,
+    slice_tuple: &TupleVector,
+    fn_name: &str,
+) -> MemberRole {
+    slice_tuple
+        .member_roles
+        .iter()
+        .find(|mr| mr.get_dim_role_gid() == dim_role.gid)
+        .cloned()
+        .unwrap_or_else(|| {
+            panic!(
+                "[mf-004] {}: no current member of DimensionRole {} in the slice tuple",
+                fn_name, dim_role.gid
+            )
+        })
+}
+
+/// 生成指定维度角色上的空成员。
+fn null_member_of(dim_role_gid: u64) -> MultiDimensionalEntity {
+    let dim_role = meta_cache::get_dimension_role_by_gid(dim_role_gid);
+    MultiDimensionalEntity::MemberRoleWrap(MemberRole::null_member(dim_role))
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstMemberFnDefaultMember {
@@ -781,12 +994,339 @@ pub enum AstMemberFnDefaultMember {
 }
 
 impl MemberRoleAccess for AstMemberFnDefaultMember {
+    /// 维度角色的默认成员由元数据服务提供，层次的默认成员为层次上配置的默认成员。
     fn resolve_member_role<'a>(
         &'a self,
-        _slice_tuple: &'a TupleVector,
-        _context: &'a mut MultiDimensionalContext,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, MemberRole> {
+        Box::pin(async move {
+            let olap_obj = match self {
+                AstMemberFnDefaultMember::Chain => outer_param,
+                AstMemberFnDefaultMember::SegsObj(segs) => {
+                    Some(segs.materialize(slice_tuple, context).await)
+                }
+            };
+
+            match olap_obj {
+                Some(MultiDimensionalEntity::DimensionRoleWrap(dim_role)) => {
+                    let member = meta_cache::get_dimension_default_member(&dim_role);
+                    MemberRole::BaseMember { dim_role, member }
+                }
+                Some(MultiDimensionalEntity::HierarchyRole(hier_role)) => MemberRole::BaseMember {
+                    member: meta_cache::get_hierarchy_default_member(hier_role.hierarchy_gid),
+                    dim_role: hier_role.dim_role,
+                },
+                _ => panic!("[dm-001] DefaultMember requires a dimension or hierarchy parameter"),
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstMemberFnStrToMember {
+    pub str_exp: AstExpression,
+}
+
+impl AstMemberFnStrToMember {
+    /// 字符串无法解析或不是一个成员时返回错误单元格值。
+    async fn do_get_member(
+        &self,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> MultiDimensionalEntity {
+        match AstSet::materialize_from_str(&self.str_exp, slice_tuple, context).await {
+            Ok(mut set) if set.tuples.len() == 1 && set.tuples[0].member_roles.len() == 1 => {
+                MultiDimensionalEntity::MemberRoleWrap(set.tuples.remove(0).member_roles.remove(0))
+            }
+            Ok(_) => MultiDimensionalEntity::VectorValue(VectorValue::Error(String::from(
+                "[str-005] StrToMember() expects exactly one member.",
+            ))),
+            Err(msg) => MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstMemberFnDateToMember {
+    pub date_exp: AstExpression,
+    pub level_segs: AstSegsObj,
+}
+
+impl MemberRoleAccess for AstMemberFnDateToMember {
+    fn resolve_member_role<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
         _outer_param: Option<MultiDimensionalEntity>,
     ) -> BoxFuture<'a, MemberRole> {
-        Box::pin(async move { todo!() })
+        Box::pin(async move {
+            let lv_role = match self.level_segs.materialize(slice_tuple, context).await {
+                MultiDimensionalEntity::LevelRole(lv_role) => lv_role,
+                _ => panic!("[dtm-002] The second parameter of DateToMember() must be a level."),
+            };
+
+            let granularity = lv_role.level.date_granularity.unwrap_or_else(|| {
+                panic!(
+                    "[dtm-003] Level {} has no date granularity, DateToMember() is not supported.",
+                    lv_role.level.gid
+                )
+            });
+
+            // 日期为空或无法解析时，与层级上没有成员覆盖这个日期一样返回空成员
+            let date = match self.date_exp.val(slice_tuple, context, None).await {
+                VectorValue::Date(date) => Some(date),
+                VectorValue::Double(seconds) => Some(OlapDate::from_unix_seconds(seconds as i64)),
+                VectorValue::Str(date_str) => OlapDate::parse(&date_str),
+                _ => None,
+            };
+            let Some(date) = date else {
+                return MemberRole::null_member(lv_role.dim_role);
+            };
+
+            let period_start = date.period_start(granularity);
+            match meta_cache::get_members_at_level(lv_role.level.gid)
+                .into_iter()
+                .find(|m| m.start_date.map(|d| d.period_start(granularity)) == Some(period_start))
+            {
+                Some(member) => MemberRole::BaseMember {
+                    dim_role: lv_role.dim_role,
+                    member,
+                },
+                None => MemberRole::null_member(lv_role.dim_role),
+            }
+        })
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum AstMemberFnDataMember {
+    Chain,
+    MemberSegs(AstSegsObj),
+}
+
+impl MemberRoleAccess for AstMemberFnDataMember {
+    /// 返回非叶子成员携带自身数据的数据成员，叶子成员返回其本身，没有数据成员时返回空成员。
+    fn resolve_member_role<'a>(
+        &'a self,
+        slice_tuple: &'a TupleVector,
+        context: &'a mut MultiDimensionalContext,
+        outer_param: Option<MultiDimensionalEntity>,
+    ) -> BoxFuture<'a, MemberRole> {
+        Box::pin(async move {
+            let member_segs = match self {
+                AstMemberFnDataMember::Chain => None,
+                AstMemberFnDataMember::MemberSegs(member_segs) => Some(member_segs),
+            };
+            let (dim_role, member) =
+                base_member_param(member_segs, outer_param, slice_tuple, context, "DataMember")
+                    .await;
+
+            if member.is_null() {
+                return MemberRole::null_member(dim_role);
+            }
+            match meta_cache::get_data_member(member.gid) {
+                Some(data_member) => MemberRole::BaseMember {
+                    dim_role,
+                    member: data_member,
+                },
+                None => MemberRole::null_member(dim_role),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AstMemberFnAncestor, AstMemberFnCousin, AstMemberFnDateToMember, AstMemberFnStrToMember,
+        AstMemberFunction, MemberRoleAccess,
+    };
+    use crate::exmdx::ast::{AstExpression, AstFactory, AstSeg, AstSegsObj, AstTerm};
+    use crate::exmdx::mdd::TupleVector;
+    use crate::mdd::{
+        Cube, MemberRole, MultiDimensionalContext, MultiDimensionalEntity, VectorValue,
+    };
+    use crate::meta_cache::test_fixture::*;
+    use crate::meta_cache::with_snapshot;
+    use std::collections::HashMap;
+
+    const JAN_3_GID: u64 = 300000000000013;
+    const FEB_2_GID: u64 = 300000000000022;
+    const JAN_2_GID: u64 = 300000000000012;
+
+    fn segs(gid: u64) -> AstSegsObj {
+        AstSegsObj {
+            segs: vec![AstSeg::Gid(DIM_ROLE_GID), AstSeg::Gid(gid)],
+        }
+    }
+
+    async fn resolve(fun: impl MemberRoleAccess) -> MemberRole {
+        with_snapshot(calendar_snapshot(), async {
+            let mut context = test_context();
+            let slice_tuple = TupleVector {
+                member_roles: vec![],
+            };
+            fun.resolve_member_role(&slice_tuple, &mut context, None)
+                .await
+        })
+        .await
+    }
+
+    fn test_context() -> MultiDimensionalContext {
+        let cube = Cube {
+            gid: CUBE_GID,
+            name: String::from("c"),
+        };
+        MultiDimensionalContext::for_test(cube, HashMap::new(), 8)
+    }
+
+    fn single_factory_exp(factory: AstFactory) -> AstExpression {
+        AstExpression {
+            terms: vec![(
+                '+',
+                AstTerm {
+                    factories: vec![('*', factory)],
+                },
+            )],
+        }
+    }
+
+    fn member_gid(member_role: &MemberRole) -> u64 {
+        match member_role {
+            MemberRole::BaseMember { member, .. } => member.gid,
+            other => panic!("unexpected member role {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn cousin_at_the_same_position() {
+        let cousin = resolve(AstMemberFnCousin::MemberSegs_AncestorMemberSegs(
+            segs(JAN_2_GID),
+            segs(FEB_GID),
+        ))
+        .await;
+        assert_eq!(member_gid(&cousin), FEB_2_GID);
+    }
+
+    #[tokio::test]
+    async fn cousin_without_member_at_the_position_is_null() {
+        // 2 月只有 2 天，没有与 1 月 3 日对应的成员
+        let cousin = resolve(AstMemberFnCousin::MemberSegs_AncestorMemberSegs(
+            segs(JAN_3_GID),
+            segs(FEB_GID),
+        ))
+        .await;
+        assert!(cousin.is_null_member());
+    }
+
+    #[tokio::test]
+    async fn cousin_under_a_deeper_member_is_null() {
+        let cousin = resolve(AstMemberFnCousin::MemberSegs_AncestorMemberSegs(
+            segs(JAN_GID),
+            segs(FEB_2_GID),
+        ))
+        .await;
+        assert!(cousin.is_null_member());
+    }
+
+    #[tokio::test]
+    async fn ancestor_by_distance() {
+        let ancestor = resolve(AstMemberFnAncestor::MemberSegs_Distance(segs(JAN_3_GID), 2)).await;
+        assert_eq!(member_gid(&ancestor), YEAR_2024_GID);
+
+        let ancestor = resolve(AstMemberFnAncestor::MemberSegs_Distance(segs(JAN_3_GID), 5)).await;
+        assert!(ancestor.is_null_member());
+    }
+
+    #[tokio::test]
+    async fn ancestor_on_a_lower_level_is_null() {
+        let ancestor = resolve(AstMemberFnAncestor::MemberSegs_LevelSegs(
+            segs(JAN_3_GID),
+            segs(MONTH_LEVEL_GID),
+        ))
+        .await;
+        assert_eq!(member_gid(&ancestor), JAN_GID);
+
+        let ancestor = resolve(AstMemberFnAncestor::MemberSegs_LevelSegs(
+            segs(JAN_GID),
+            segs(DAY_LEVEL_GID),
+        ))
+        .await;
+        assert!(ancestor.is_null_member());
+    }
+
+    fn date_to_day(factory: AstFactory) -> AstMemberFnDateToMember {
+        AstMemberFnDateToMember {
+            date_exp: single_factory_exp(factory),
+            level_segs: segs(DAY_LEVEL_GID),
+        }
+    }
+
+    #[tokio::test]
+    async fn date_to_member_finds_the_covering_member() {
+        let day = resolve(date_to_day(AstFactory::String(String::from("2024-02-02")))).await;
+        assert_eq!(member_gid(&day), FEB_2_GID);
+    }
+
+    #[tokio::test]
+    async fn date_to_member_with_an_invalid_date_is_null() {
+        for date in [
+            AstFactory::String(String::from("not a date")),
+            AstFactory::String(String::new()),
+            AstFactory::Null,
+            // 层级上没有覆盖这个日期的成员
+            AstFactory::String(String::from("2024-03-01")),
+        ] {
+            let day = resolve(date_to_day(date.clone())).await;
+            assert!(day.is_null_member(), "{:?}: {:?}", date, day);
+        }
+    }
+
+    async fn str_to_member(mdx_str: &str) -> MultiDimensionalEntity {
+        let str_fn = AstMemberFunction::StrToMember(AstMemberFnStrToMember {
+            str_exp: single_factory_exp(AstFactory::String(String::from(mdx_str))),
+        });
+        with_snapshot(calendar_snapshot(), async {
+            let mut context = test_context();
+            let slice_tuple = TupleVector {
+                member_roles: vec![],
+            };
+            str_fn.get_member(None, &slice_tuple, &mut context).await
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn str_to_member_parses_a_unique_name() {
+        match str_to_member("&600000000000001.&300000000000002").await {
+            MultiDimensionalEntity::MemberRoleWrap(member_role) => {
+                assert_eq!(member_gid(&member_role), JAN_GID)
+            }
+            other => panic!("unexpected entity {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn str_to_member_reports_invalid_strings_as_errors() {
+        for (mdx_str, code) in [
+            (
+                "&600000000000001.&300000000000002} on 0 from [c]",
+                "[str-001]",
+            ),
+            (
+                "&600000000000001.&300000000000002, &600000000000001.&300000000000003",
+                "[str-005]",
+            ),
+        ] {
+            match str_to_member(mdx_str).await {
+                MultiDimensionalEntity::VectorValue(VectorValue::Error(msg)) => {
+                    assert!(msg.starts_with(code), "{}", msg)
+                }
+                other => panic!("{} is accepted: {:?}", mdx_str, other),
+            }
+        }
     }
 }
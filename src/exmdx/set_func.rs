@@ -1,4 +1,5 @@
 use crate::exmdx::ast::AstSegsObj;
+use crate::exmdx::ast::{AstExpression, AstSet};
 
 use crate::exmdx::mdd::TupleVector;
 
@@ -6,6 +7,7 @@ use crate::mdd::MultiDimensionalContext;
 use crate::mdd::{MemberRole, MultiDimensionalEntity, Set};
 
 use crate::exmdx::ast::Materializable;
+use crate::meta_cache;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstSetFunction {
@@ -42,6 +44,7 @@ pub enum AstSetFunction {
     Generate	(	AstSetFnGenerate	),
     Head	(	AstSetFnHead	),
     Subset	(	AstSetFnSubset	),
+    StrToSet(AstSetFnStrToSet),
 }
 
 impl AstSetFunction {
@@ -50,14 +53,18 @@ impl AstSetFunction {
         left_unique_param: Option<MultiDimensionalEntity>,
         slice_tuple: &TupleVector,
         context: &mut MultiDimensionalContext,
-    ) -> Set {
+    ) -> Result<Set, String> {
         match self {
             AstSetFunction::Children(AstSetFnChildren::Chain) => {
-                AstSetFnChildren::do_get_set(left_unique_param, context).await
+                Ok(AstSetFnChildren::do_get_set(left_unique_param))
             }
             AstSetFunction::Children(AstSetFnChildren::MemSegs(segs)) => {
                 let mem_role = segs.materialize(slice_tuple, context).await;
-                AstSetFnChildren::do_get_set(Some(mem_role), context).await
+                Ok(AstSetFnChildren::do_get_set(Some(mem_role)))
+            }
+            // 字符串无法解析为集合时返回错误，由调用方作为错误单元格值返回
+            AstSetFunction::StrToSet(str_to_set) => {
+                AstSet::materialize_from_str(&str_to_set.str_exp, slice_tuple, context).await
             }
             _ => todo!("AstSetFunction::get_set() [HI-SHUA-927381]"),
         }
@@ -71,17 +78,10 @@ pub enum AstSetFnChildren {
 }
 
 impl AstSetFnChildren {
-    async fn do_get_set(
-        left_unique_param: Option<MultiDimensionalEntity>,
-        context: &mut MultiDimensionalContext,
-    ) -> Set {
+    fn do_get_set(left_unique_param: Option<MultiDimensionalEntity>) -> Set {
         if let MultiDimensionalEntity::MemberRoleWrap(mr) = left_unique_param.unwrap() {
             if let MemberRole::BaseMember { dim_role, member } = mr {
-                let children = context
-                    .grpc_client
-                    .get_child_members_by_gid(member.gid)
-                    .await
-                    .unwrap();
+                let children = meta_cache::get_visible_children(member.gid);
 
                 let tuples: Vec<TupleVector> = children
                     .into_iter()
@@ -101,6 +101,11 @@ impl AstSetFnChildren {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstSetFnStrToSet {
+    pub str_exp: AstExpression,
+}
+
 #[allow(non_camel_case_types)]	#[derive(Clone, Debug, PartialEq)]	pub enum 	AstSetFnBottomPercent	{ WillTodo, }
 #[allow(non_camel_case_types)]	#[derive(Clone, Debug, PartialEq)]	pub enum 	AstSetFnCrossJoin	{ WillTodo, }
 #[allow(non_camel_case_types)]	#[derive(Clone, Debug, PartialEq)]	pub enum 	AstSetFnDescendants	{ WillTodo, }
@@ -0,0 +1,52 @@
+use crate::exmdx::ast::AstExpression;
+use crate::exmdx::ast::AstSet;
+
+use crate::exmdx::mdd::TupleVector;
+
+use crate::mdd::{MultiDimensionalContext, MultiDimensionalEntity};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AstTupleFunction {
+    StrToTuple(AstTupFnStrToTuple),
+}
+
+impl AstTupleFunction {
+    pub async fn get_tuple(
+        &self,
+        left_outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> Result<TupleVector, String> {
+        match self {
+            AstTupleFunction::StrToTuple(str_to_tuple) => {
+                str_to_tuple
+                    .do_get_tuple(left_outer_param, slice_tuple, context)
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstTupFnStrToTuple {
+    pub str_exp: AstExpression,
+}
+
+impl AstTupFnStrToTuple {
+    async fn do_get_tuple(
+        &self,
+        _left_outer_param: Option<MultiDimensionalEntity>,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> Result<TupleVector, String> {
+        // 字符串无法解析或不是一个元组时返回错误，由调用方作为错误单元格值返回
+        let mut set = AstSet::materialize_from_str(&self.str_exp, slice_tuple, context).await?;
+        if set.tuples.len() != 1 {
+            return Err(format!(
+                "[str-004] StrToTuple() expects exactly one tuple, but got {}.",
+                set.tuples.len()
+            ));
+        }
+        Ok(set.tuples.remove(0))
+    }
+}
@@ -1,11 +1,12 @@
 mod agg_service_client;
 // mod core;
-mod cache;
 mod exmdx;
 mod mdd;
 mod meta_cache;
 mod olapmeta_grpc_client;
 mod permission;
+mod plan_cache;
+mod result_cache;
 
 mod euclidolap {
     tonic::include_proto!("euclidolap");
@@ -17,6 +18,8 @@ use crate::exmdx::ast::AstMdxStatement;
 use crate::exmdx::mdd::TupleVector;
 
 pub mod calcul;
+pub mod calendar;
+pub mod cell_format;
 pub mod cfg;
 // pub mod mdx_ast;
 pub mod mdx_lexer;
@@ -25,22 +28,23 @@ pub mod mdx_tokens;
 lalrpop_mod!(pub mdx_grammar);
 
 use euclidolap::olap_api_server::{OlapApi, OlapApiServer};
+use euclidolap::{GrpcOlapAxis, GrpcOlapAxisMember, GrpcOlapAxisTuple};
 use euclidolap::{GrpcOlapVector, OlapRequest, OlapResponse};
 use mdd::VectorValue;
 use tonic::{transport::Server, Request, Response, Status};
 
 use lalrpop_util::lalrpop_mod;
 
-use crate::mdx_grammar::MdxStatementParser;
-
-use crate::mdx_lexer::Lexer as MdxLexer;
+use crate::result_cache::STATEMENT_CACHE;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    cache::meta::reload().await;
-
+    // 加载元数据，配置了快照文件时从文件启动，不等待元数据服务
     meta_cache::init().await;
 
+    // 按配置定期或在收到变化通知时重新加载元数据
+    meta_cache::spawn_reloaders();
+
     // let addr = "127.0.0.1:50052".parse().unwrap();
     let addr = "0.0.0.0:50052".parse().unwrap();
 
@@ -75,55 +79,103 @@ impl OlapApi for EuclidOLAPService {
         //     olap_request.user_name
         // );
 
-        let (_cube_gid, cell_vals) =
+        let (_cube_gid, grpc_olap_vectors, axes) =
             handle_stat(operation_type, statement, olap_request.user_name).await;
 
-        let grpc_olap_vectors: Vec<GrpcOlapVector> = cell_vals
-            .iter()
-            .map(|cell| match cell {
-                VectorValue::Double(val) => GrpcOlapVector {
-                    null_flag: false,
-                    val: *val,
-                    str: format!("{}", *val),
-                },
-                VectorValue::Str(str) => GrpcOlapVector {
-                    null_flag: false,
-                    val: 0.0,
-                    str: String::from(str),
-                },
-                VectorValue::Null => GrpcOlapVector {
-                    null_flag: true,
-                    val: 0.0,
-                    str: String::from(""),
-                },
-                VectorValue::Invalid => GrpcOlapVector {
-                    null_flag: false,
-                    val: 0.0,
-                    str: String::from("Invalid"),
-                },
-            })
-            .collect();
-
         let olap_resp = OlapResponse {
             vectors: grpc_olap_vectors,
+            axes,
         };
 
         Ok(Response::new(olap_resp))
     }
 }
 
+/// 单元格的原始值，以及按 FORMAT_STRING 格式化后的文本。
+fn to_grpc_olap_vector(cell: &VectorValue, format_string: Option<&str>) -> GrpcOlapVector {
+    let formatted_value = cell_format::format_cell(cell, format_string);
+    match cell {
+        VectorValue::Double(val) => GrpcOlapVector {
+            null_flag: false,
+            val: *val,
+            str: format!("{}", *val),
+            error_flag: false,
+            formatted_value,
+        },
+        VectorValue::Str(str) => GrpcOlapVector {
+            null_flag: false,
+            val: 0.0,
+            str: String::from(str),
+            error_flag: false,
+            formatted_value,
+        },
+        VectorValue::Date(date) => GrpcOlapVector {
+            null_flag: false,
+            val: date.unix_seconds() as f64,
+            str: date.to_string(),
+            error_flag: false,
+            formatted_value,
+        },
+        VectorValue::Null => GrpcOlapVector {
+            null_flag: true,
+            val: 0.0,
+            str: String::from(""),
+            error_flag: false,
+            formatted_value,
+        },
+        VectorValue::Error(msg) => GrpcOlapVector {
+            null_flag: true,
+            val: 0.0,
+            str: msg.clone(),
+            error_flag: true,
+            formatted_value,
+        },
+        VectorValue::Invalid => GrpcOlapVector {
+            null_flag: false,
+            val: 0.0,
+            str: String::from("Invalid"),
+            error_flag: false,
+            formatted_value,
+        },
+    }
+}
+
 async fn handle_stat(
     optype: String,
     statement: String,
     user_name: String,
-) -> (u64, Vec<VectorValue>) {
+) -> (u64, Vec<GrpcOlapVector>, Vec<GrpcOlapAxis>) {
     match optype.as_str() {
         "MDX" => {
-            let ast_selstat = MdxStatementParser::new()
-                .parse(MdxLexer::new(&statement))
-                .unwrap();
-
-            exe_md_query(ast_selstat, user_name).await
+            // 先取缓存版本再固定元数据快照，查询期间重新加载元数据时结果不会被缓存
+            let version = result_cache::current_version();
+            meta_cache::with_pinned_snapshot(exe_mdx(statement, user_name, version)).await
+        }
+        "RELOAD_METADATA" => {
+            let cell = if !permission::is_admin_user(&user_name) {
+                VectorValue::Error(format!(
+                    "[auth-001] User {} is not allowed to reload metadata.",
+                    user_name
+                ))
+            } else {
+                match meta_cache::reload().await {
+                    Ok(true) => VectorValue::Str(String::from("Metadata reloaded.")),
+                    Ok(false) => VectorValue::Str(String::from("Metadata unchanged.")),
+                    Err(msg) => VectorValue::Error(msg),
+                }
+            };
+            (0, vec![to_grpc_olap_vector(&cell, None)], vec![])
+        }
+        "IMPORT_MEASURE_DATA" => {
+            // statement 为度量数据文件路径，导入成功后缓存的结果全部失效
+            let cell = match agg_service_client::AggServiceGrpcClient::get_cli()
+                .import_measure_data(statement)
+                .await
+            {
+                Ok(message) => VectorValue::Str(message),
+                Err(msg) => VectorValue::Error(msg),
+            };
+            (0, vec![to_grpc_olap_vector(&cell, None)], vec![])
         }
         _ => {
             panic!(
@@ -134,15 +186,104 @@ async fn handle_stat(
     }
 }
 
-async fn exe_md_query(ast_selstat: AstMdxStatement, user_name: String) -> (u64, Vec<VectorValue>) {
-    let mut context = ast_selstat
-        .gen_md_context(UserAccessesCollection::new(String::from(user_name)).await)
-        .await;
+async fn exe_mdx(
+    statement: String,
+    user_name: String,
+    version: result_cache::CacheVersion,
+) -> (u64, Vec<GrpcOlapVector>, Vec<GrpcOlapAxis>) {
+    let user_acol = match UserAccessesCollection::new(user_name).await {
+        Ok(user_acol) => user_acol,
+        Err(msg) => {
+            return (
+                0,
+                vec![to_grpc_olap_vector(&VectorValue::Error(msg), None)],
+                vec![],
+            )
+        }
+    };
+
+    // 同一天内相同语句、相同权限的查询直接返回缓存的结果，不再解析和计算
+    let cache_key = (
+        result_cache::normalize_statement(&statement),
+        user_acol.fingerprint(),
+        calendar::today(),
+    );
+    if let Some(result) = STATEMENT_CACHE.lock().unwrap().get(&cache_key, version) {
+        return result;
+    }
+
+    let ast_selstat = plan_cache::parse_statement(&statement);
+
+    let result = exe_md_query(&ast_selstat, user_acol).await;
+
+    // 含有错误单元格的结果（例如聚合服务暂时不可用）不缓存，
+    // 计算期间跨过了零点的结果可能用了新的日期，也不缓存
+    if !result.1.iter().any(|vector| vector.error_flag) && calendar::today() == cache_key.2 {
+        let bytes = result_cache::statement_entry_bytes(&cache_key.0, &result);
+        STATEMENT_CACHE
+            .lock()
+            .unwrap()
+            .insert(cache_key, result.clone(), version, bytes);
+    }
+    result
+}
+
+async fn exe_md_query(
+    ast_selstat: &AstMdxStatement,
+    user_acol: UserAccessesCollection,
+) -> (u64, Vec<GrpcOlapVector>, Vec<GrpcOlapAxis>) {
+    let mut context = ast_selstat.gen_md_context(user_acol).await;
     let axes = ast_selstat.build_axes(&mut context).await;
     let coordinates: Vec<TupleVector> = mdd::Axis::axis_vec_cartesian_product(&axes, &context);
 
-    (
-        context.cube.gid,
-        calcul::calculate(coordinates, &mut context).await,
-    )
+    let format_strings: Vec<Option<String>> = coordinates
+        .iter()
+        .map(|cord| calcul::cell_format_string(cord, &context))
+        .collect();
+
+    let cell_vals = calcul::calculate(coordinates, &mut context).await;
+    let grpc_olap_vectors = cell_vals
+        .iter()
+        .zip(format_strings.iter())
+        .map(|(cell, format_string)| to_grpc_olap_vector(cell, format_string.as_deref()))
+        .collect();
+    let grpc_axes = axes
+        .iter()
+        .map(|axis| to_grpc_olap_axis(axis, &context))
+        .collect();
+
+    (context.cube.gid, grpc_olap_vectors, grpc_axes)
+}
+
+/// 轴上每个成员的唯一名称、名称，以及 DIMENSION PROPERTIES 请求的属性（成员没有的属性不返回）。
+fn to_grpc_olap_axis(axis: &mdd::Axis, context: &mdd::MultiDimensionalContext) -> GrpcOlapAxis {
+    let tuples = axis
+        .set
+        .tuples
+        .iter()
+        .map(|tuple| GrpcOlapAxisTuple {
+            members: tuple
+                .member_roles
+                .iter()
+                .map(|member_role| GrpcOlapAxisMember {
+                    unique_name: member_role.unique_name(context),
+                    name: member_role.name(context),
+                    properties: axis
+                        .dim_properties
+                        .iter()
+                        .filter_map(|prop_name| {
+                            member_role
+                                .property(prop_name, context)
+                                .map(|prop_val| (prop_name.clone(), prop_val))
+                        })
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    GrpcOlapAxis {
+        position: axis.pos_num,
+        tuples,
+    }
 }
@@ -6,12 +6,15 @@ use crate::meta_cache;
 use crate::exmdx::ast::{AstExpression, AstSeg};
 use crate::exmdx::exp_func::AstExpFunction;
 
-use crate::exmdx::ast::{AstCustomObject, AstSegsObj};
+use crate::exmdx::ast::{AstCustomObject, AstFormulaMemberProps, AstSegsObj};
 use crate::exmdx::mdd::TupleVector;
 
 use crate::permission::UserAccessesCollection;
 
+use crate::calendar::{DateGranularity, OlapDate};
+
 use crate::olapmeta_grpc_client::olapmeta::UniversalOlapEntity;
+use crate::agg_service_client::CellKey;
 use crate::olapmeta_grpc_client::GrpcClient;
 use std::collections::HashMap;
 use std::ops;
@@ -55,12 +58,13 @@ pub enum MultiDimensionalEntity {
     MemberRoleWrap(MemberRole),
     FormulaMemberWrap {
         dim_role_gid: u64,
+        frml_gid: u64,
         exp: AstExpression,
     },
     VectorValue(VectorValue),
     Cube(Cube),
-    // Dimension(Dimension), // 维度实体
-    // Hierarchy(Hierarchy), // 层次实体
+    // Dimension(Dimension), // 维度实体，在 Cube 中以 DimensionRoleWrap 表示
+    HierarchyRole(HierarchyRole), // 层次实体
     Nothing,
 }
 
@@ -68,8 +72,11 @@ pub enum MultiDimensionalEntity {
 pub enum VectorValue {
     Double(f64),
     Str(String),
+    Date(OlapDate),
     Null,
     Invalid,
+    // 计算出错的单元格，例如计算成员循环引用，携带错误信息
+    Error(String),
 }
 
 // VectorValue + VectorValue
@@ -78,6 +85,7 @@ impl ops::Add for VectorValue {
 
     fn add(self, other: VectorValue) -> VectorValue {
         match (self, other) {
+            (VectorValue::Error(msg), _) | (_, VectorValue::Error(msg)) => VectorValue::Error(msg),
             (VectorValue::Double(num_1), VectorValue::Double(num_2)) => {
                 VectorValue::Double(num_1 + num_2)
             }
@@ -90,6 +98,11 @@ impl ops::Add for VectorValue {
             (VectorValue::Str(str_1), VectorValue::Str(str_2)) => {
                 VectorValue::Str(format!("{}{}", str_1, str_2))
             }
+            // 日期 + 天数
+            (VectorValue::Date(date), VectorValue::Double(days))
+            | (VectorValue::Double(days), VectorValue::Date(date)) => {
+                VectorValue::Date(OlapDate::from_days(date.days() + days as i64))
+            }
             _ => VectorValue::Invalid,
         }
     }
@@ -101,9 +114,18 @@ impl ops::Sub for VectorValue {
 
     fn sub(self, other: VectorValue) -> VectorValue {
         match (self, other) {
+            (VectorValue::Error(msg), _) | (_, VectorValue::Error(msg)) => VectorValue::Error(msg),
             (VectorValue::Double(num_1), VectorValue::Double(num_2)) => {
                 VectorValue::Double(num_1 - num_2)
             }
+            // 日期 - 天数
+            (VectorValue::Date(date), VectorValue::Double(days)) => {
+                VectorValue::Date(OlapDate::from_days(date.days() - days as i64))
+            }
+            // 两个日期相差的天数
+            (VectorValue::Date(date_1), VectorValue::Date(date_2)) => {
+                VectorValue::Double((date_1.days() - date_2.days()) as f64)
+            }
             _ => VectorValue::Invalid,
         }
     }
@@ -115,6 +137,7 @@ impl ops::Mul for VectorValue {
 
     fn mul(self, other: VectorValue) -> VectorValue {
         match (self, other) {
+            (VectorValue::Error(msg), _) | (_, VectorValue::Error(msg)) => VectorValue::Error(msg),
             (VectorValue::Double(num_1), VectorValue::Double(num_2)) => {
                 VectorValue::Double(num_1 * num_2)
             }
@@ -129,6 +152,7 @@ impl ops::Div for VectorValue {
 
     fn div(self, other: VectorValue) -> VectorValue {
         match (self, other) {
+            (VectorValue::Error(msg), _) | (_, VectorValue::Error(msg)) => VectorValue::Error(msg),
             (VectorValue::Double(num_1), VectorValue::Double(num_2)) => {
                 if num_2 == 0.0 {
                     VectorValue::Invalid
@@ -141,8 +165,101 @@ impl ops::Div for VectorValue {
     }
 }
 
+// VectorValue % VectorValue
+impl ops::Rem for VectorValue {
+    type Output = VectorValue;
+
+    fn rem(self, other: VectorValue) -> VectorValue {
+        match (self, other) {
+            (VectorValue::Error(msg), _) | (_, VectorValue::Error(msg)) => VectorValue::Error(msg),
+            (VectorValue::Double(num_1), VectorValue::Double(num_2)) => {
+                if num_2 == 0.0 {
+                    VectorValue::Invalid
+                } else {
+                    VectorValue::Double(num_1 % num_2)
+                }
+            }
+            _ => VectorValue::Invalid,
+        }
+    }
+}
+
+// -VectorValue
+impl ops::Neg for VectorValue {
+    type Output = VectorValue;
+
+    fn neg(self) -> VectorValue {
+        match self {
+            VectorValue::Double(num) => VectorValue::Double(-num),
+            VectorValue::Null | VectorValue::Error(_) => self,
+            _ => VectorValue::Invalid,
+        }
+    }
+}
+
 impl VectorValue {
+    // +VectorValue，只适用于数值
+    pub fn unary_plus(self) -> VectorValue {
+        match self {
+            VectorValue::Double(_) | VectorValue::Null | VectorValue::Error(_) => self,
+            _ => VectorValue::Invalid,
+        }
+    }
+
+    // VectorValue ^ VectorValue，结果不是有效数值（例如负数开平方）时返回 Invalid
+    pub fn pow(self, exponent: VectorValue) -> VectorValue {
+        match (self, exponent) {
+            (VectorValue::Error(msg), _) | (_, VectorValue::Error(msg)) => VectorValue::Error(msg),
+            (VectorValue::Double(base), VectorValue::Double(exponent)) => {
+                let result = base.powf(exponent);
+                if result.is_finite() {
+                    VectorValue::Double(result)
+                } else {
+                    VectorValue::Invalid
+                }
+            }
+            _ => VectorValue::Invalid,
+        }
+    }
+
+    // VectorValue || VectorValue 或 VectorValue & VectorValue，Null 按空字符串连接
+    pub fn concat(self, other: VectorValue) -> VectorValue {
+        if let VectorValue::Error(_) = self {
+            return self;
+        }
+        if let VectorValue::Error(_) = other {
+            return other;
+        }
+        match (self.concat_str(), other.concat_str()) {
+            (Some(str_1), Some(str_2)) => VectorValue::Str(format!("{}{}", str_1, str_2)),
+            _ => VectorValue::Invalid,
+        }
+    }
+
+    fn concat_str(self) -> Option<String> {
+        match self {
+            VectorValue::Double(num) => Some(format!("{}", num)),
+            VectorValue::Str(str) => Some(str),
+            VectorValue::Date(date) => Some(date.to_string()),
+            VectorValue::Null => Some(String::new()),
+            VectorValue::Invalid | VectorValue::Error(_) => None,
+        }
+    }
+
     pub fn logical_cmp(&self, op: &String, other: &VectorValue) -> bool {
+        // 与 Null 比较时，Null 按 0 或空字符串处理
+        match (self, other) {
+            (VectorValue::Null, VectorValue::Str(_)) => {
+                return VectorValue::Str(String::new()).logical_cmp(op, other)
+            }
+            (VectorValue::Str(_), VectorValue::Null) => {
+                return self.logical_cmp(op, &VectorValue::Str(String::new()))
+            }
+            (VectorValue::Null, _) => return VectorValue::Double(0.0).logical_cmp(op, other),
+            (_, VectorValue::Null) => return self.logical_cmp(op, &VectorValue::Double(0.0)),
+            _ => {}
+        }
+
         match (self, other) {
             (VectorValue::Double(a), VectorValue::Double(b)) => match op.as_str() {
                 "<" => a < b,
@@ -162,6 +279,15 @@ impl VectorValue {
                 ">=" => a >= b,
                 _ => false,
             },
+            (VectorValue::Date(a), VectorValue::Date(b)) => match op.as_str() {
+                "<" => a < b,
+                "<=" => a <= b,
+                "=" => a == b,
+                "<>" => a != b,
+                ">" => a > b,
+                ">=" => a >= b,
+                _ => false,
+            },
             _ => false,
         }
     }
@@ -181,6 +307,9 @@ impl MultiDimensionalEntity {
                 parent_gid: entity.parent_gid,
                 leaf: entity.leaf,
                 full_path: entity.member_gid_full_path.clone(),
+                start_date: OlapDate::parse(&entity.start_date),
+                data_member: entity.data_member,
+                properties: entity.properties.clone(),
             }),
             _ => {
                 panic!("Unsupported entity class: {}", entity.olap_entity_class);
@@ -213,6 +342,14 @@ pub trait MultiDimensionalEntityLocator {
     ) -> MultiDimensionalEntity;
 }
 
+/// 计算成员预求值阶段收集基本单元格坐标，缓存中没有的单元格先以 Null 占位。
+#[derive(Debug, Default)]
+pub struct BaseCellCollector {
+    pub requests: Vec<TupleVector>,
+    // 以 Null 占位的值的个数，求值前后不变时预求值的结果就是最终结果
+    pub placeholders: usize,
+}
+
 #[derive(Debug)]
 pub struct MultiDimensionalContext {
     pub cube: Cube,
@@ -222,23 +359,111 @@ pub struct MultiDimensionalContext {
     pub grpc_client: GrpcClient,
     pub formulas_map: HashMap<u64, AstCustomObject>,
     pub user_acol: UserAccessesCollection,
+    // 正在求值的计算成员及其 Tuple，用于检测循环引用
+    pub eval_stack: Vec<(u64, TupleVector)>,
+    pub max_eval_depth: usize,
+    // 计算成员预求值阶段的收集状态，为 None 时不在收集阶段
+    pub base_cell_collector: Option<BaseCellCollector>,
+    // 本次查询已取得的基本单元格值，按聚合服务坐标缓存，避免同一单元格重复请求
+    pub cell_cache: HashMap<CellKey, VectorValue>,
 }
 
 impl MultiDimensionalContext {
+    /// 开始对计算成员求值。同一个计算成员在同一个 Tuple 上已经在求值时为循环引用，
+    /// 与求值深度超出 max_eval_depth 一样返回错误单元格值，不再继续递归。
+    pub fn enter_formula(
+        &mut self,
+        dim_role_gid: u64,
+        frml_gid: u64,
+        tuple: &TupleVector,
+    ) -> Result<(), VectorValue> {
+        if self
+            .eval_stack
+            .iter()
+            .any(|(gid, active_tuple)| *gid == frml_gid && active_tuple == tuple)
+        {
+            return Err(VectorValue::Error(format!(
+                "[rc-001] Infinite recursion: calculated member &{}.&{} references itself",
+                dim_role_gid, frml_gid
+            )));
+        }
+        if self.eval_stack.len() >= self.max_eval_depth {
+            return Err(VectorValue::Error(format!(
+                "[rc-002] Maximum evaluation depth {} exceeded at calculated member &{}.&{}",
+                self.max_eval_depth, dim_role_gid, frml_gid
+            )));
+        }
+
+        self.eval_stack.push((frml_gid, tuple.clone()));
+        Ok(())
+    }
+
+    pub fn exit_formula(&mut self) {
+        self.eval_stack.pop();
+    }
+
+    /// LookupCube 在另一个 Cube 的上下文中求值时，接续当前的求值栈和深度限制，
+    /// 经由 LookupCube 的循环引用同样能被检测到。
+    pub fn enter_tunnel(&mut self, tunnel: &mut MultiDimensionalContext) {
+        tunnel.eval_stack = std::mem::take(&mut self.eval_stack);
+        tunnel.max_eval_depth = self.max_eval_depth;
+    }
+
+    pub fn exit_tunnel(&mut self, tunnel: &mut MultiDimensionalContext) {
+        self.eval_stack = std::mem::take(&mut tunnel.eval_stack);
+    }
+
+    /// 单元测试使用的上下文，元数据服务客户端不会真正建立连接。
+    #[cfg(test)]
+    pub fn for_test(
+        cube: Cube,
+        formulas_map: HashMap<u64, AstCustomObject>,
+        max_eval_depth: usize,
+    ) -> Self {
+        MultiDimensionalContext {
+            cube,
+            query_slice_tuple: TupleVector {
+                member_roles: vec![],
+            },
+            grpc_client: GrpcClient::lazy("http://127.0.0.1:1"),
+            formulas_map,
+            user_acol: UserAccessesCollection::default(),
+            eval_stack: vec![],
+            max_eval_depth,
+            base_cell_collector: None,
+            cell_cache: HashMap::new(),
+        }
+    }
+
     pub async fn find_entity_by_gid(&mut self, gid: u64) -> MultiDimensionalEntity {
         match GidType::entity_type(gid) {
             GidType::DimensionRole => {
-                let dim_role = self
-                    .grpc_client
-                    .get_dimension_role_by_gid(gid)
-                    .await
-                    .unwrap();
+                let dim_role = meta_cache::get_dimension_role_by_gid(gid);
                 MultiDimensionalEntity::DimensionRoleWrap(dim_role)
             }
             GidType::Cube => {
                 let cube = meta_cache::get_cube_by_gid(gid);
                 MultiDimensionalEntity::Cube(cube)
             }
+            GidType::Hierarchy => {
+                // 单独使用层次时，由层次所属维度在当前 Cube 中唯一的维度角色确定
+                let hierarchy = meta_cache::get_hierarchy_by_gid(gid);
+                let dim_roles: Vec<DimensionRole> =
+                    meta_cache::get_dimension_roles_by_cube_gid(self.cube.gid)
+                        .into_iter()
+                        .filter(|dr| dr.dimension_gid == hierarchy.dimension_gid)
+                        .collect();
+                if dim_roles.len() != 1 {
+                    panic!(
+                        "[hr-101] Hierarchy {} matches {} dimension roles in cube {}, use [DimensionRole].[Hierarchy] instead.",
+                        gid,
+                        dim_roles.len(),
+                        self.cube.gid
+                    );
+                }
+                let dim_role = dim_roles.into_iter().next().unwrap();
+                MultiDimensionalEntity::HierarchyRole(HierarchyRole::new(dim_role, gid))
+            }
             _ => {
                 panic!(
                     "Invalid gid type provided. Expected DimensionRole but found a different type."
@@ -252,11 +477,13 @@ impl MultiDimensionalContext {
             "MultiDimensionalContext >>>>>>>>>>>>>>>>>>>>>>>>>>>>>> find_entity_by_str({})",
             seg
         );
-        let dim_role = self
-            .grpc_client
-            .get_dimension_role_by_name(self.cube.gid, seg)
-            .await
-            .unwrap();
+        let dim_role =
+            meta_cache::find_dimension_role_by_name(self.cube.gid, seg).unwrap_or_else(|| {
+                panic!(
+                    "[nm-000] DimensionRole [{}] not found in cube {}.",
+                    seg, self.cube.gid
+                )
+            });
         MultiDimensionalEntity::DimensionRoleWrap(dim_role)
     }
 }
@@ -266,6 +493,18 @@ pub struct Set {
     pub tuples: Vec<TupleVector>,
 }
 
+impl Set {
+    /// 生成可以被 StrToSet 重新解析的字符串，形如 `{(m1, m2), (m3, m4)}`。
+    pub fn to_mdx_str(&self, context: &MultiDimensionalContext) -> String {
+        let tuples: Vec<String> = self
+            .tuples
+            .iter()
+            .map(|tuple| tuple.to_mdx_str(context))
+            .collect();
+        format!("{{{}}}", tuples.join(", "))
+    }
+}
+
 impl MultiDimensionalEntityLocator for Set {
     async fn locate_entity(
         &self,
@@ -364,22 +603,51 @@ impl MultiDimensionalEntityLocator for Set {
         }
     }
 
+    /// 在集合的成员中按 gid 查找成员。
     async fn locate_entity_by_gid(
         &self,
-        _gid: u64,
+        gid: u64,
         _slice_tuple: &TupleVector,
         _context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
-        todo!()
+        for tuple in self.tuples.iter() {
+            for mr in tuple.member_roles.iter() {
+                if let MemberRole::BaseMember { member, .. } = mr {
+                    if member.gid == gid {
+                        return MultiDimensionalEntity::MemberRoleWrap(mr.clone());
+                    }
+                }
+            }
+        }
+        panic!("[nm-010] Member {} is not in the set.", gid);
     }
 
+    /// 在集合的成员中按名称（不区分大小写）查找成员，多个成员同名时报错。
     async fn locate_entity_by_seg(
         &self,
-        _seg: &String,
+        seg: &String,
         _slice_tuple: &TupleVector,
         _context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
-        todo!()
+        let mut matched: Vec<MemberRole> = Vec::new();
+        for tuple in self.tuples.iter() {
+            for mr in tuple.member_roles.iter() {
+                if let MemberRole::BaseMember { member, .. } = mr {
+                    if meta_cache::names_match(&member.name, seg) && !matched.contains(mr) {
+                        matched.push(mr.clone());
+                    }
+                }
+            }
+        }
+
+        match matched.len() {
+            0 => panic!("[nm-011] Member [{}] is not in the set.", seg),
+            1 => MultiDimensionalEntity::MemberRoleWrap(matched.pop().unwrap()),
+            n => panic!(
+                "[nm-012] Member name [{}] is ambiguous in the set: {} members match, use &gid[name] to disambiguate.",
+                seg, n
+            ),
+        }
     }
 }
 
@@ -393,6 +661,227 @@ impl LevelRole {
     pub fn new(dim_role: DimensionRole, level: Level) -> Self {
         LevelRole { dim_role, level }
     }
+
+    /// 定位层级后面的成员路径，例如 `[Time].[Year].[2024].[Q1]` 中的 `[2024].[Q1]`：
+    /// 第一段是这个层级上的成员，其余的段在这个成员下继续定位。
+    pub async fn locate_member(
+        &self,
+        segs: &AstSegsObj,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> MultiDimensionalEntity {
+        let member = match segs.segs.first() {
+            Some(AstSeg::Gid(gid)) | Some(AstSeg::GidStr(gid, _)) => {
+                let member = meta_cache::get_member_by_gid(*gid);
+                if member.level_gid != self.level.gid {
+                    panic!(
+                        "[nm-006] Member {} is not on level {}.",
+                        gid, self.level.gid
+                    );
+                }
+                member
+            }
+            Some(AstSeg::Str(seg)) => {
+                let candidates = meta_cache::get_level_members_by_name(self.level.gid, seg);
+                let scope = format!("level {}[{}]", self.level.gid, self.level.name);
+                pick_member_by_name(candidates, seg, &scope)
+                    .unwrap_or_else(|| panic!("[nm-005] Member [{}] not found on {}.", seg, scope))
+            }
+            _ => panic!("[nm-007] LevelRole::locate_member() Unsupported segment."),
+        };
+
+        let member_role = MemberRole::BaseMember {
+            dim_role: self.dim_role.clone(),
+            member,
+        };
+        if segs.segs.len() == 1 {
+            return MultiDimensionalEntity::MemberRoleWrap(member_role);
+        }
+        let tail_segs = AstSegsObj {
+            segs: (segs.segs[1..].to_vec()),
+        };
+        member_role
+            .locate_entity(&tail_segs, slice_tuple, context)
+            .await
+    }
+}
+
+/// 维度角色上的层次，由 member.Hierarchy、level.Hierarchy 等函数得到。
+#[derive(Debug, Clone, PartialEq)]
+pub struct HierarchyRole {
+    pub dim_role: DimensionRole,
+    pub hierarchy_gid: u64,
+}
+
+impl HierarchyRole {
+    pub fn new(dim_role: DimensionRole, hierarchy_gid: u64) -> Self {
+        HierarchyRole {
+            dim_role,
+            hierarchy_gid,
+        }
+    }
+}
+
+impl MultiDimensionalEntityLocator for HierarchyRole {
+    async fn locate_entity(
+        &self,
+        segs: &AstSegsObj,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> MultiDimensionalEntity {
+        let seg_list = &segs.segs;
+
+        let seg = seg_list.first().unwrap();
+        let entity = match seg {
+            AstSeg::Gid(gid) | AstSeg::GidStr(gid, _) => {
+                self.locate_entity_by_gid(*gid, slice_tuple, context).await
+            }
+            AstSeg::Str(seg) => self.locate_entity_by_seg(seg, slice_tuple, context).await,
+            AstSeg::LevelFunc(level_fn) => MultiDimensionalEntity::LevelRole(
+                level_fn
+                    .get_level_role(
+                        Some(MultiDimensionalEntity::HierarchyRole(self.clone())),
+                        slice_tuple,
+                        context,
+                    )
+                    .await,
+            ),
+            AstSeg::MemberFunc(member_fn) => {
+                member_fn
+                    .get_member(
+                        Some(MultiDimensionalEntity::HierarchyRole(self.clone())),
+                        slice_tuple,
+                        context,
+                    )
+                    .await
+            }
+            _ => panic!("[hr-001] HierarchyRole::locate_entity() Unsupported segment."),
+        };
+
+        match entity {
+            MultiDimensionalEntity::MemberRoleWrap(member_role) => {
+                if seg_list.len() == 1 {
+                    return MultiDimensionalEntity::MemberRoleWrap(member_role);
+                }
+
+                let tail_segs = AstSegsObj {
+                    segs: (seg_list[1..].to_vec()),
+                };
+                member_role
+                    .locate_entity(&tail_segs, slice_tuple, context)
+                    .await
+            }
+            MultiDimensionalEntity::LevelRole(lv_role) => {
+                if seg_list.len() == 1 {
+                    return MultiDimensionalEntity::LevelRole(lv_role);
+                }
+
+                // [Dim].[Hierarchy].[Level].[Member]
+                let tail_segs = AstSegsObj {
+                    segs: (seg_list[1..].to_vec()),
+                };
+                lv_role
+                    .locate_member(&tail_segs, slice_tuple, context)
+                    .await
+            }
+            _ => panic!("[hr-001] HierarchyRole::locate_entity() Unsupported entity class."),
+        }
+    }
+
+    async fn locate_entity_by_gid(
+        &self,
+        gid: u64,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
+    ) -> MultiDimensionalEntity {
+        // 空成员的唯一名称 [DimRole].[Hierarchy].&[0]
+        if gid == 0 {
+            return MultiDimensionalEntity::MemberRoleWrap(MemberRole::null_member(
+                self.dim_role.clone(),
+            ));
+        }
+
+        let entity = self
+            .dim_role
+            .locate_entity_by_gid(gid, slice_tuple, context)
+            .await;
+
+        // 成员和层级必须属于当前层次
+        let hierarchy_gid = match &entity {
+            MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember { member, .. }) => {
+                meta_cache::get_level_by_gid(member.level_gid).hierarchy_gid
+            }
+            MultiDimensionalEntity::LevelRole(lv_role) => lv_role.level.hierarchy_gid,
+            _ => panic!("[hr-002] HierarchyRole::locate_entity_by_gid() Unsupported entity class."),
+        };
+        if hierarchy_gid != self.hierarchy_gid {
+            panic!(
+                "[hr-003] Entity {} does not belong to hierarchy {}",
+                gid, self.hierarchy_gid
+            );
+        }
+        entity
+    }
+
+    /// 名称先按层次上的层级名称匹配，再按层次上的成员名称匹配，均不区分大小写。
+    async fn locate_entity_by_seg(
+        &self,
+        seg: &String,
+        _slice_tuple: &TupleVector,
+        _context: &mut MultiDimensionalContext,
+    ) -> MultiDimensionalEntity {
+        let mut levels: Vec<Level> = meta_cache::get_hierarchy_levels(self.hierarchy_gid)
+            .into_iter()
+            .filter(|lv| meta_cache::names_match(&lv.name, seg))
+            .collect();
+        match levels.len() {
+            0 => {}
+            1 => {
+                return MultiDimensionalEntity::LevelRole(LevelRole::new(
+                    self.dim_role.clone(),
+                    levels.pop().unwrap(),
+                ))
+            }
+            n => panic!(
+                "[nm-002] Level name [{}] is ambiguous in hierarchy {}: {} levels match.",
+                seg, self.hierarchy_gid, n
+            ),
+        }
+
+        let candidates = meta_cache::get_hierarchy_members_by_name(self.hierarchy_gid, seg);
+        let scope = format!("hierarchy {}", self.hierarchy_gid);
+        match pick_member_by_name(candidates, seg, &scope) {
+            Some(member) => MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+                dim_role: self.dim_role.clone(),
+                member,
+            }),
+            None => panic!(
+                "[nm-004] Member [{}] not found in hierarchy {}.",
+                seg, self.hierarchy_gid
+            ),
+        }
+    }
+}
+
+/// 在按层级深度排列的同名候选成员中取层级最浅的一个。
+/// 最浅的层级上有多个同名成员时名称有歧义，需要使用完整路径或 `&gid[name]`。
+fn pick_member_by_name(candidates: Vec<Member>, name: &str, scope: &str) -> Option<Member> {
+    let top_level = candidates.first()?.level;
+    let mut nearest: Vec<Member> = candidates
+        .into_iter()
+        .filter(|m| m.level == top_level)
+        .collect();
+
+    if nearest.len() > 1 {
+        let gids: Vec<String> = nearest.iter().map(|m| format!("&{}", m.gid)).collect();
+        panic!(
+            "[nm-003] Member name [{}] is ambiguous in {}: members {} match, use a longer path or &gid[name] to disambiguate.",
+            name,
+            scope,
+            gids.join(", ")
+        );
+    }
+    nearest.pop()
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -403,22 +892,132 @@ pub enum MemberRole {
     },
     FormulaMember {
         dim_role_gid: u64,
+        // WITH 中定义的计算成员 gid，用于查找它的定义和属性
+        frml_gid: u64,
         exp: AstExpression,
     },
 }
 
 impl MemberRole {
+    pub fn null_member(dim_role: DimensionRole) -> Self {
+        MemberRole::BaseMember {
+            dim_role,
+            member: Member::null_member(),
+        }
+    }
+
+    pub fn is_null_member(&self) -> bool {
+        matches!(self, MemberRole::BaseMember { member, .. } if member.is_null())
+    }
+
     pub fn get_dim_role_gid(&self) -> u64 {
         match self {
             MemberRole::BaseMember { dim_role, .. } => dim_role.gid,
+            MemberRole::FormulaMember { dim_role_gid, .. } => *dim_role_gid,
+        }
+    }
+
+    /// 生成可以被 StrToMember 重新解析的成员唯一名称。
+    ///
+    /// 基本成员：`[<DimRole name>].[<Hierarchy name>].&[<Member gid>]`，空成员的 gid 为 0
+    /// 公式成员：`[<DimRole name>].&[<FormulaMember gid>]`
+    pub fn unique_name(&self, _context: &MultiDimensionalContext) -> String {
+        match self {
+            MemberRole::BaseMember { dim_role, member } => {
+                let hierarchy_gid = if member.is_null() {
+                    dim_role.default_hierarchy_gid
+                } else {
+                    meta_cache::get_level_by_gid(member.level_gid).hierarchy_gid
+                };
+                format!(
+                    "{}.{}.&[{}]",
+                    bracketed_name(&dim_role.name),
+                    bracketed_name(&meta_cache::get_hierarchy_by_gid(hierarchy_gid).name),
+                    member.gid
+                )
+            }
             MemberRole::FormulaMember {
                 dim_role_gid,
-                exp: _,
-            } => *dim_role_gid,
+                frml_gid,
+                ..
+            } => {
+                let dim_role = meta_cache::get_dimension_role_by_gid(*dim_role_gid);
+                format!("{}.&[{}]", bracketed_name(&dim_role.name), frml_gid)
+            }
+        }
+    }
+
+    pub fn name(&self, context: &MultiDimensionalContext) -> String {
+        match self {
+            MemberRole::BaseMember { member, .. } => member.name.clone(),
+            MemberRole::FormulaMember { frml_gid, .. } => {
+                let (segs, _) = formula_member_definition(*frml_gid, context);
+                match segs.segs.last() {
+                    Some(AstSeg::GidStr(_, name)) | Some(AstSeg::Str(name)) => name.clone(),
+                    _ => String::new(),
+                }
+            }
+        }
+    }
+
+    /// 计算成员的 SOLVE_ORDER，基本成员没有 SOLVE_ORDER，返回 None。
+    pub fn solve_order(&self, context: &MultiDimensionalContext) -> Option<i32> {
+        match self {
+            MemberRole::BaseMember { .. } => None,
+            MemberRole::FormulaMember { frml_gid, .. } => {
+                Some(formula_member_definition(*frml_gid, context).1.solve_order)
+            }
+        }
+    }
+
+    /// 计算成员使用 FORMAT_STRING 属性，基本成员（例如度量）使用成员属性 FORMAT_STRING。
+    pub fn format_string(&self, context: &MultiDimensionalContext) -> Option<String> {
+        match self {
+            MemberRole::BaseMember { member, .. } => member.property("FORMAT_STRING"),
+            MemberRole::FormulaMember { frml_gid, .. } => {
+                formula_member_definition(*frml_gid, context)
+                    .1
+                    .format_string
+                    .clone()
+            }
+        }
+    }
+
+    /// 成员属性值，计算成员只有 NAME、CAPTION 和 UNIQUE_NAME 等内置属性。
+    pub fn property(&self, name: &str, context: &MultiDimensionalContext) -> Option<String> {
+        match name.to_ascii_uppercase().as_str() {
+            "UNIQUE_NAME" | "MEMBER_UNIQUE_NAME" => return Some(self.unique_name(context)),
+            "NAME" | "MEMBER_NAME" | "CAPTION" | "MEMBER_CAPTION" => {
+                return Some(self.name(context))
+            }
+            _ => {}
+        }
+        match self {
+            MemberRole::BaseMember { member, .. } if !member.is_null() => member.property(name),
+            _ => None,
         }
     }
 }
 
+/// 在当前上下文的 WITH 定义中按 gid 查找计算成员，返回其定义路径和属性。
+fn formula_member_definition(
+    frml_gid: u64,
+    context: &MultiDimensionalContext,
+) -> (&AstSegsObj, &AstFormulaMemberProps) {
+    match context.formulas_map.get(&frml_gid) {
+        Some(AstCustomObject::FormulaMember(segs, _, props)) => (segs, props),
+        _ => panic!(
+            "[un-001] Formula member {} is not defined in the current context.",
+            frml_gid
+        ),
+    }
+}
+
+/// 将名称包装为 `[name]`，名称中的 `]` 按词法规则转义为 `]]`。
+fn bracketed_name(name: &str) -> String {
+    format!("[{}]", name.replace(']', "]]"))
+}
+
 impl MultiDimensionalEntityLocator for MemberRole {
     async fn locate_entity(
         &self,
@@ -431,22 +1030,39 @@ impl MultiDimensionalEntityLocator for MemberRole {
         let seg = seg_list.first().unwrap();
         match seg {
             AstSeg::MemberFunc(member_fn) => {
-                member_fn
+                let entity = member_fn
                     .get_member(
                         Some(MultiDimensionalEntity::MemberRoleWrap(self.clone())),
                         slice_tuple,
                         context,
                     )
-                    .await
+                    .await;
+
+                match entity {
+                    // 继续在结果成员上定位剩余的段，例如 CurrentMember.Parent.Name
+                    MultiDimensionalEntity::MemberRoleWrap(member_role) if seg_list.len() > 1 => {
+                        let tail_segs = AstSegsObj {
+                            segs: (seg_list[1..].to_vec()),
+                        };
+                        Box::pin(member_role.locate_entity(&tail_segs, slice_tuple, context)).await
+                    }
+                    entity => entity,
+                }
             }
             AstSeg::SetFunc(set_fn) => {
-                let set = set_fn
+                let set = match set_fn
                     .get_set(
                         Some(MultiDimensionalEntity::MemberRoleWrap(self.clone())),
                         slice_tuple,
                         context,
                     )
-                    .await;
+                    .await
+                {
+                    Ok(set) => set,
+                    Err(msg) => {
+                        return MultiDimensionalEntity::VectorValue(VectorValue::Error(msg))
+                    }
+                };
 
                 if seg_list.len() == 1 {
                     MultiDimensionalEntity::SetWrap(set)
@@ -484,26 +1100,109 @@ impl MultiDimensionalEntityLocator for MemberRole {
                     .await;
                 MultiDimensionalEntity::VectorValue(cell_val)
             }
+            AstSeg::HierarchyFunc(hier_fn) => {
+                let entity = hier_fn
+                    .get_entity(
+                        Some(MultiDimensionalEntity::MemberRoleWrap(self.clone())),
+                        slice_tuple,
+                        context,
+                    )
+                    .await;
+
+                if seg_list.len() == 1 {
+                    return entity;
+                }
+
+                let tail_segs = AstSegsObj {
+                    segs: (seg_list[1..].to_vec()),
+                };
+                match entity {
+                    MultiDimensionalEntity::DimensionRoleWrap(dim_role) => {
+                        Box::pin(dim_role.locate_entity(&tail_segs, slice_tuple, context)).await
+                    }
+                    MultiDimensionalEntity::HierarchyRole(hier_role) => {
+                        Box::pin(hier_role.locate_entity(&tail_segs, slice_tuple, context)).await
+                    }
+                    _ => panic!("[hf-002] MemberRole::locate_entity() Unsupported entity class."),
+                }
+            }
+            AstSeg::Gid(_) | AstSeg::GidStr(_, _) | AstSeg::Str(_) => {
+                let entity = match seg {
+                    AstSeg::Str(seg_str) => {
+                        self.locate_entity_by_seg(seg_str, slice_tuple, context)
+                            .await
+                    }
+                    _ => {
+                        self.locate_entity_by_gid(seg.get_gid().unwrap(), slice_tuple, context)
+                            .await
+                    }
+                };
+
+                match entity {
+                    // [Product].[Electronics].[Phones]
+                    MultiDimensionalEntity::MemberRoleWrap(member_role) if seg_list.len() > 1 => {
+                        let tail_segs = AstSegsObj {
+                            segs: (seg_list[1..].to_vec()),
+                        };
+                        Box::pin(member_role.locate_entity(&tail_segs, slice_tuple, context)).await
+                    }
+                    entity => entity,
+                }
+            }
             _ => panic!("Panic in MemberRole::locate_entity() .. 67HUSran .."),
         }
     }
 
+    /// 按 gid 定位当前成员的后代成员。
     async fn locate_entity_by_gid(
         &self,
-        _gid: u64,
+        gid: u64,
         _slice_tuple: &TupleVector,
         _context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
-        todo!("MemberRole::locate_entity_by_gid() not implemented yet.")
+        let MemberRole::BaseMember { dim_role, member } = self else {
+            panic!(
+                "[nm-020] Cannot locate member {} under a formula member.",
+                gid
+            );
+        };
+
+        let descendant = meta_cache::get_member_by_gid(gid);
+        if descendant.gid == member.gid || !descendant.full_path.contains(&member.gid) {
+            panic!(
+                "[nm-021] Member {} is not a descendant of member {}.",
+                gid, member.gid
+            );
+        }
+        MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+            dim_role: dim_role.clone(),
+            member: descendant,
+        })
     }
 
+    /// 按名称（不区分大小写）定位当前成员的后代成员，优先匹配层级最浅的后代（通常是子成员）。
     async fn locate_entity_by_seg(
         &self,
-        _seg: &String,
+        seg: &String,
         _slice_tuple: &TupleVector,
         _context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
-        todo!("MemberRole::locate_entity_by_seg() not implemented yet.")
+        let MemberRole::BaseMember { dim_role, member } = self else {
+            panic!(
+                "[nm-020] Cannot locate member [{}] under a formula member.",
+                seg
+            );
+        };
+
+        let candidates = meta_cache::get_descendants_by_name(member.gid, seg);
+        let scope = format!("member {}[{}]", member.gid, member.name);
+        match pick_member_by_name(candidates, seg, &scope) {
+            Some(descendant) => MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+                dim_role: dim_role.clone(),
+                member: descendant,
+            }),
+            None => panic!("[nm-022] Member [{}] not found under {}.", seg, scope),
+        }
     }
 }
 
@@ -516,13 +1215,63 @@ pub struct Level {
     pub hierarchy_gid: u64,
     pub opening_period_gid: u64,
     pub closing_period_gid: u64,
+    pub date_granularity: Option<DateGranularity>,
+    pub hide_member_if: HideMemberIf,
+}
+
+/// 不规则（ragged）层次中隐藏占位成员的规则，被隐藏的成员不出现在导航结果中，
+/// 其子成员直接显示在其父成员之下。
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HideMemberIf {
+    #[default]
+    Never,
+    NoName,
+    ParentName,
+    OnlyChildWithNoName,
+    OnlyChildWithParentName,
+}
+
+impl HideMemberIf {
+    /// 不区分大小写，忽略下划线，例如 `ONLY_CHILD_WITH_NO_NAME`、`OnlyChildWithNoName`；空串或无法识别时为 Never。
+    pub fn parse(s: &str) -> Self {
+        match s.replace('_', "").to_ascii_uppercase().as_str() {
+            "NONAME" => HideMemberIf::NoName,
+            "PARENTNAME" => HideMemberIf::ParentName,
+            "ONLYCHILDWITHNONAME" => HideMemberIf::OnlyChildWithNoName,
+            "ONLYCHILDWITHPARENTNAME" => HideMemberIf::OnlyChildWithParentName,
+            _ => HideMemberIf::Never,
+        }
+    }
+
+    /// `siblings` 为成员在父成员下（不含数据成员）的兄弟数量，包含成员自身。
+    pub fn hides(&self, member: &Member, parent: Option<&Member>, siblings: usize) -> bool {
+        let no_name = member.name.trim().is_empty();
+        let parent_name = parent.is_some_and(|p| p.name == member.name);
+        match self {
+            HideMemberIf::Never => false,
+            HideMemberIf::NoName => no_name,
+            HideMemberIf::ParentName => parent_name,
+            HideMemberIf::OnlyChildWithNoName => siblings == 1 && no_name,
+            HideMemberIf::OnlyChildWithParentName => siblings == 1 && parent_name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hierarchy {
+    pub gid: u64,
+    pub name: String,
+    pub dimension_gid: u64,
+    pub default_member_gid: u64,
+    // 父子层次：成员深度随分支变化，非叶子成员可以通过数据成员携带自身的数据
+    pub parent_child: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DimensionRole {
     pub gid: u64,
-    // pub name: String,
-    // pub cube_gid: u64,
+    pub name: String,
+    pub cube_gid: u64,
     pub dimension_gid: u64,
     pub default_hierarchy_gid: u64,
     pub measure_flag: bool,
@@ -535,7 +1284,7 @@ impl MultiDimensionalEntityLocator for DimensionRole {
         slice_tuple: &TupleVector,
         context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
-        let seg_list = &segs.segs;
+        let seg_list = &segs.segs[..];
 
         let seg = seg_list.iter().next().unwrap();
         let entity = match seg {
@@ -577,11 +1326,31 @@ impl MultiDimensionalEntityLocator for DimensionRole {
                     .locate_entity(&tail_segs, slice_tuple, context)
                     .await
             }
+            // [Dim].[Level].[Member]
             MultiDimensionalEntity::LevelRole(lv_role) => {
                 if seg_list.len() == 1 {
                     return MultiDimensionalEntity::LevelRole(lv_role);
                 }
-                todo!("[HDJS8840] DimensionRole::locate_entity() LevelRole not implemented yet.")
+
+                let tail_segs = AstSegsObj {
+                    segs: (seg_list[1..].to_vec()),
+                };
+                lv_role
+                    .locate_member(&tail_segs, slice_tuple, context)
+                    .await
+            }
+            // [Dim].[Hierarchy].[Member]
+            MultiDimensionalEntity::HierarchyRole(hier_role) => {
+                if seg_list.len() == 1 {
+                    return MultiDimensionalEntity::HierarchyRole(hier_role);
+                }
+
+                let tail_segs = AstSegsObj {
+                    segs: (seg_list[1..].to_vec()),
+                };
+                hier_role
+                    .locate_entity(&tail_segs, slice_tuple, context)
+                    .await
             }
             _ => {
                 panic!("[DimRole] locate_entity() Unsupported entity class.");
@@ -593,48 +1362,72 @@ impl MultiDimensionalEntityLocator for DimensionRole {
         &self,
         gid: u64,
         _slice_tuple: &TupleVector,
-        context: &mut MultiDimensionalContext,
+        _context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
         match GidType::entity_type(gid) {
             GidType::Member => {
-                // let dim_gid = self.dimension_gid;
-                let olap_entity = context
-                    .grpc_client
-                    .locate_universal_olap_entity_by_gid(self.gid, gid)
-                    .await
-                    .unwrap();
-
-                match olap_entity {
-                    MultiDimensionalEntity::MemberWrap(member) => {
-                        let member_role = MemberRole::BaseMember {
-                            dim_role: self.clone(),
-                            member,
-                        };
-                        // return MultiDimensionalEntity::MemberRoleWrap(member_role);
-                        MultiDimensionalEntity::MemberRoleWrap(member_role)
-                    }
-                    _ => {
-                        panic!("[DimRole] locate_entity_by_gid() Unsupported entity class.");
+                let member = match meta_cache::find_member_by_gid(gid) {
+                    Some(member)
+                        if meta_cache::member_dimension_gid(&member)
+                            == Some(self.dimension_gid) =>
+                    {
+                        member
                     }
-                }
+                    _ => panic!(
+                        "[dr-001] Member {} does not belong to the dimension of DimensionRole {}",
+                        gid, self.gid
+                    ),
+                };
+                MultiDimensionalEntity::MemberRoleWrap(MemberRole::BaseMember {
+                    dim_role: self.clone(),
+                    member,
+                })
             }
             GidType::Level => {
                 let level = meta_cache::get_level_by_gid(gid);
                 MultiDimensionalEntity::LevelRole(LevelRole::new(self.clone(), level))
             }
+            GidType::Hierarchy => {
+                let hierarchy = meta_cache::get_hierarchy_by_gid(gid);
+                if hierarchy.dimension_gid != self.dimension_gid {
+                    panic!(
+                        "[hr-004] Hierarchy {} does not belong to the dimension of DimensionRole {}",
+                        gid, self.gid
+                    );
+                }
+                MultiDimensionalEntity::HierarchyRole(HierarchyRole::new(self.clone(), gid))
+            }
             _ => {
                 todo!("Unsupported entity type.");
             }
         }
     }
 
+    /// 名称先按维度上的层次名称匹配，其余名称在默认层次上按层级、成员的顺序匹配，均不区分大小写。
     async fn locate_entity_by_seg(
         &self,
-        _seg: &String,
-        _slice_tuple: &TupleVector,
-        _context: &mut MultiDimensionalContext,
+        seg: &String,
+        slice_tuple: &TupleVector,
+        context: &mut MultiDimensionalContext,
     ) -> MultiDimensionalEntity {
-        todo!("DimensionRole::locate_entity_by_seg() not implemented yet.");
+        let mut hierarchies: Vec<Hierarchy> =
+            meta_cache::get_hierarchies_by_dimension(self.dimension_gid)
+                .into_iter()
+                .filter(|h| meta_cache::names_match(&h.name, seg))
+                .collect();
+        match hierarchies.len() {
+            0 => HierarchyRole::new(self.clone(), self.default_hierarchy_gid)
+                .locate_entity_by_seg(seg, slice_tuple, context)
+                .await,
+            1 => MultiDimensionalEntity::HierarchyRole(HierarchyRole::new(
+                self.clone(),
+                hierarchies.pop().unwrap().gid,
+            )),
+            n => panic!(
+                "[nm-001] Hierarchy name [{}] is ambiguous in DimensionRole {}: {} hierarchies match.",
+                seg, self.gid, n
+            ),
+        }
     }
 }
 
@@ -653,6 +1446,53 @@ pub struct Member {
     pub measure_index: u32,
     pub leaf: bool,
     pub full_path: Vec<u64>,
+    pub start_date: Option<OlapDate>,
+    // 父子层次中非叶子成员自身的数据成员，不出现在 Children、Members 等导航结果中，通过 DataMember 访问
+    pub data_member: bool,
+    // 用户自定义的成员属性，例如 Color、Size，通过 Properties 和 DIMENSION PROPERTIES 访问
+    pub properties: HashMap<String, String>,
+}
+
+impl Member {
+    /// 空成员（gid 为 0），表示不存在的成员，例如 ParallelPeriod 越界时的结果。
+    /// 包含空成员的 Tuple 计算结果为 Null。
+    pub fn null_member() -> Self {
+        Member {
+            gid: 0,
+            name: String::new(),
+            level_gid: 0,
+            level: 0,
+            parent_gid: 0,
+            measure_index: 0,
+            leaf: true,
+            full_path: vec![],
+            start_date: None,
+            data_member: false,
+            properties: HashMap::new(),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.gid == 0
+    }
+
+    /// 按名称（不区分大小写）取成员属性值，先匹配内置属性，再匹配用户自定义属性，
+    /// 不存在时返回 None。
+    pub fn property(&self, name: &str) -> Option<String> {
+        match name.to_ascii_uppercase().as_str() {
+            "NAME" | "MEMBER_NAME" | "CAPTION" | "MEMBER_CAPTION" => {
+                return Some(self.name.clone())
+            }
+            "KEY" | "MEMBER_KEY" | "ID" => return Some(self.gid.to_string()),
+            "LEVEL_NUMBER" => return Some(self.level.to_string()),
+            "PARENT_KEY" if self.parent_gid != 0 => return Some(self.parent_gid.to_string()),
+            _ => {}
+        }
+        self.properties
+            .iter()
+            .find(|(key, _)| meta_cache::names_match(key, name))
+            .map(|(_, val)| val.clone())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -711,6 +1551,7 @@ impl MultiDimensionalEntityLocator for Cube {
 pub struct Axis {
     pub set: Set,
     pub pos_num: u32,
+    pub dim_properties: Vec<String>,
 }
 
 impl Axis {
@@ -767,3 +1608,148 @@ impl Axis {
         ov_coordinates
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Cube, MemberRole, MultiDimensionalContext, VectorValue};
+    use crate::calcul::cell_format_string;
+    use crate::exmdx::ast::AstCustomObject;
+    use crate::exmdx::mdd::TupleVector;
+    use crate::mdx_grammar::MdxStatementParser;
+    use crate::mdx_lexer::Lexer;
+
+    const DIM_ROLE_GID: u64 = 600000000000001;
+    const FRML_GID: u64 = 700000000000001;
+    const MAX_EVAL_DEPTH: usize = 8;
+
+    fn context_with_formula(max_eval_depth: usize) -> MultiDimensionalContext {
+        // [x] 的定义引用了它自己
+        context_from_mdx(
+            "with member &600000000000001.&700000000000001[x] as &600000000000001.&700000000000001[x] \
+             select [a] on 0 from [c]",
+            max_eval_depth,
+        )
+    }
+
+    fn context_from_mdx(mdx: &str, max_eval_depth: usize) -> MultiDimensionalContext {
+        let ast = MdxStatementParser::new().parse(Lexer::new(mdx)).unwrap();
+        let formulas_map = ast
+            .custom_objs
+            .iter()
+            .map(|cus_obj| match cus_obj {
+                AstCustomObject::FormulaMember(segs, _, _) => {
+                    (segs.get_last_gid().unwrap(), cus_obj.clone())
+                }
+                other => panic!("unexpected custom object {:?}", other),
+            })
+            .collect();
+        let cube = Cube {
+            gid: 0,
+            name: String::from("c"),
+        };
+        MultiDimensionalContext::for_test(cube, formulas_map, max_eval_depth)
+    }
+
+    fn formula_member(context: &MultiDimensionalContext, frml_gid: u64) -> MemberRole {
+        match &context.formulas_map[&frml_gid] {
+            AstCustomObject::FormulaMember(_, exp, _) => MemberRole::FormulaMember {
+                dim_role_gid: DIM_ROLE_GID,
+                frml_gid,
+                exp: exp.clone(),
+            },
+            other => panic!("unexpected custom object {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn self_reference_is_detected_through_lookup_cube() {
+        let mut outer = context_with_formula(MAX_EVAL_DEPTH);
+        let mut tunnel = context_with_formula(1);
+        let tuple = TupleVector {
+            member_roles: vec![],
+        };
+
+        assert!(outer.enter_formula(DIM_ROLE_GID, FRML_GID, &tuple).is_ok());
+        outer.enter_tunnel(&mut tunnel);
+        assert_eq!(tunnel.max_eval_depth, MAX_EVAL_DEPTH);
+        match tunnel.enter_formula(DIM_ROLE_GID, FRML_GID, &tuple) {
+            Err(VectorValue::Error(msg)) => assert!(msg.starts_with("[rc-001]"), "{}", msg),
+            other => panic!("self reference is not detected: {:?}", other),
+        }
+        outer.exit_tunnel(&mut tunnel);
+
+        assert_eq!(outer.eval_stack.len(), 1);
+        assert!(tunnel.eval_stack.is_empty());
+    }
+
+    #[tokio::test]
+    async fn solve_order_of_name_first_formula_member() {
+        let context = context_from_mdx(
+            "with member [Measures].&700000000000001[x] as 1, SOLVE_ORDER = 5 \
+             select [a] on 0 from [c]",
+            MAX_EVAL_DEPTH,
+        );
+        let mr = formula_member(&context, FRML_GID);
+        assert_eq!(mr.solve_order(&context), Some(5));
+        assert_eq!(mr.name(&context), "x");
+    }
+
+    #[tokio::test]
+    async fn formula_members_with_equal_expressions_keep_their_own_solve_order() {
+        let context = context_from_mdx(
+            "with member &600000000000001.&700000000000001[x] as 1, SOLVE_ORDER = 1 \
+             member &600000000000001.&700000000000002[y] as 1, SOLVE_ORDER = 2 \
+             select [a] on 0 from [c]",
+            MAX_EVAL_DEPTH,
+        );
+        let x = formula_member(&context, 700000000000001);
+        let y = formula_member(&context, 700000000000002);
+        assert_eq!(x.solve_order(&context), Some(1));
+        assert_eq!(y.solve_order(&context), Some(2));
+    }
+
+    #[tokio::test]
+    async fn formula_members_with_equal_expressions_keep_their_own_format_string() {
+        let context = context_from_mdx(
+            "with member &600000000000001.&700000000000001[x] as 1, FORMAT_STRING = \"0.00\" \
+             member &600000000000001.&700000000000002[y] as 1, FORMAT_STRING = \"#,##0\" \
+             select [a] on 0 from [c]",
+            MAX_EVAL_DEPTH,
+        );
+        let format_of = |frml_gid| {
+            let cord = TupleVector {
+                member_roles: vec![formula_member(&context, frml_gid)],
+            };
+            cell_format_string(&cord, &context)
+        };
+        assert_eq!(format_of(700000000000001), Some(String::from("0.00")));
+        assert_eq!(format_of(700000000000002), Some(String::from("#,##0")));
+    }
+
+    #[tokio::test]
+    async fn formula_members_with_equal_expressions_are_not_mistaken_for_recursion() {
+        let mut context = context_from_mdx(
+            "with member &600000000000001.&700000000000001[x] as 1 \
+             member &600000000000001.&700000000000002[y] as 1 \
+             select [a] on 0 from [c]",
+            MAX_EVAL_DEPTH,
+        );
+        let tuple = TupleVector {
+            member_roles: vec![],
+        };
+
+        assert!(context
+            .enter_formula(DIM_ROLE_GID, 700000000000001, &tuple)
+            .is_ok());
+        assert!(context
+            .enter_formula(DIM_ROLE_GID, 700000000000002, &tuple)
+            .is_ok());
+        match context.enter_formula(DIM_ROLE_GID, 700000000000001, &tuple) {
+            Err(VectorValue::Error(msg)) => assert!(msg.starts_with("[rc-001]"), "{}", msg),
+            other => panic!("self reference is not detected: {:?}", other),
+        }
+        context.exit_formula();
+        context.exit_formula();
+        assert!(context.eval_stack.is_empty());
+    }
+}
@@ -27,3 +27,136 @@ impl Iterator for Lexer<'_> {
             .map(|(token, span)| Ok((span.start, token?, span.end)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Lexer;
+    use crate::exmdx::ast::{AstCustomObject, AstExpression, AstFactory, AstTerm};
+    use crate::mdx_grammar::MdxStatementParser;
+
+    /// 将表达式作为计算成员的定义解析，返回它的语法树。
+    fn parse_exp(exp: &str) -> AstExpression {
+        let mdx = format!(
+            "with member &600000000000001.&700000000000001[x] as {} select [a] on 0 from [c]",
+            exp
+        );
+        let ast = MdxStatementParser::new()
+            .parse(Lexer::new(&mdx))
+            .unwrap_or_else(|e| panic!("can not parse {}: {:?}", exp, e));
+        match ast.custom_objs.into_iter().next() {
+            Some(AstCustomObject::FormulaMember(_, exp, _)) => exp,
+            other => panic!("unexpected custom object {:?}", other),
+        }
+    }
+
+    fn num(n: f64) -> AstFactory {
+        AstFactory::Numeric(n)
+    }
+
+    fn neg(factory: AstFactory) -> AstFactory {
+        AstFactory::Unary('-', Box::new(factory))
+    }
+
+    fn pow(base: AstFactory, exponent: AstFactory) -> AstFactory {
+        AstFactory::Power(Box::new(base), Box::new(exponent))
+    }
+
+    fn term(factories: Vec<AstFactory>) -> AstTerm {
+        AstTerm {
+            factories: factories.into_iter().map(|f| ('*', f)).collect(),
+        }
+    }
+
+    fn exp(terms: Vec<(char, AstTerm)>) -> AstExpression {
+        AstExpression { terms }
+    }
+
+    #[test]
+    fn binary_minus_binds_looser_than_multiplication() {
+        let expected = exp(vec![
+            ('+', term(vec![num(1.0)])),
+            ('-', term(vec![num(2.0), num(3.0)])),
+        ]);
+        assert_eq!(parse_exp("1-2*3"), expected);
+        assert_eq!(parse_exp("1 -2 * 3"), expected);
+    }
+
+    #[test]
+    fn binary_minus_binds_looser_than_power() {
+        assert_eq!(
+            parse_exp("1-2^2"),
+            exp(vec![
+                ('+', term(vec![num(1.0)])),
+                ('-', term(vec![pow(num(2.0), num(2.0))])),
+            ])
+        );
+    }
+
+    #[test]
+    fn unary_minus_applies_to_doubles() {
+        assert_eq!(
+            parse_exp("-2.5"),
+            exp(vec![('+', term(vec![neg(num(2.5))]))])
+        );
+        assert_eq!(
+            parse_exp("2 * -2.5"),
+            exp(vec![('+', term(vec![num(2.0), neg(num(2.5))]))])
+        );
+    }
+
+    fn parse_with(with: &str) -> Result<Vec<AstCustomObject>, String> {
+        let mdx = format!("with {} select [a] on 0 from [c]", with);
+        MdxStatementParser::new()
+            .parse(Lexer::new(&mdx))
+            .map(|ast| ast.custom_objs)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    #[test]
+    fn solve_order_is_range_checked() {
+        let member = "member &600000000000001.&700000000000001[x] as 1";
+        match parse_with(&format!("{}, solve_order = -2147483648", member)).as_deref() {
+            Ok([AstCustomObject::FormulaMember(_, _, props)]) => {
+                assert_eq!(props.solve_order, i32::MIN)
+            }
+            other => panic!("unexpected parse result {:?}", other),
+        }
+        assert!(parse_with(&format!("{}, solve_order = 2147483648", member)).is_err());
+        assert!(parse_with(&format!("{}, solve_order = -2147483649", member)).is_err());
+    }
+
+    #[test]
+    fn member_properties_must_follow_a_member() {
+        assert!(parse_with("set [s] as {[a]}, solve_order = 1").is_err());
+        assert!(parse_with("set [s] as {[a]}, format_string = \"0.00\"").is_err());
+        let objs = parse_with(
+            "member &600000000000001.&700000000000001[x] as 1, format_string = \"0.00\", \
+             set [s] as {[a]}",
+        )
+        .unwrap();
+        assert_eq!(objs.len(), 2);
+    }
+
+    #[test]
+    fn quoted_string_unescapes_double_quotes() {
+        let objs = parse_with(
+            "member &600000000000001.&700000000000001[x] as 1, \
+             format_string = \"0.00\"\" pcs\"\"\"",
+        )
+        .unwrap();
+        match objs.as_slice() {
+            [AstCustomObject::FormulaMember(_, _, props)] => {
+                assert_eq!(props.format_string.as_deref(), Some("0.00\" pcs\""))
+            }
+            other => panic!("unexpected custom objects {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unary_minus_binds_looser_than_power() {
+        assert_eq!(
+            parse_exp("-2^2"),
+            exp(vec![('+', term(vec![neg(pow(num(2.0), num(2.0)))]))])
+        );
+    }
+}
@@ -6,6 +6,8 @@ use std::num::{ParseFloatError, ParseIntError};
 pub enum LexicalError {
     InvalidInteger(ParseIntError),
     InvalidDouble(ParseFloatError),
+    // 整数超出了取值范围，例如 SOLVE_ORDER 超出 i32
+    IntegerOutOfRange(i128),
     #[default]
     InvalidToken,
 }
@@ -79,15 +81,15 @@ pub enum Token {
         raw[1..raw.len() - 1].replace("]]", "]")
     })]
     BracketedString(String),
-    #[regex(r#""[^"]*""#, |lex| {
+    // 字符串中的 "" 表示一个双引号
+    #[regex(r#""(?:[^"]|"")*""#, |lex| {
         let raw = lex.slice();
-        raw[1..raw.len() - 1].to_string()
+        raw[1..raw.len() - 1].replace("\"\"", "\"")
     })]
     QuotedString(String),
+    // 数值字面量都是无符号的，负号由语法中的一元运算符处理
     #[regex("[0-9]+", |lex| lex.slice().parse())]
     Integer(u64),
-    #[regex(r"-[0-9]+", |lex| lex.slice().parse())]
-    NegativeInteger(i64),
     #[regex(r"[0-9]+\.[0-9]+", |lex| lex.slice().parse())]
     Double(f64),
 
@@ -104,6 +106,10 @@ pub enum Token {
     Divided,
     #[token("%")]
     Percent,
+    #[token("^")]
+    Caret,
+    #[token("||")]
+    DoublePipe,
 
     #[token("{")]
     CurlyBraceLeft,
@@ -284,6 +290,78 @@ pub enum Token {
     // #####################################################
     #[regex("(?i)Name")]
     Name,
+    #[regex("(?i)Upper")]
+    Upper,
+    #[regex("(?i)Lower")]
+    Lower,
+    #[regex("(?i)Left")]
+    Left,
+    #[regex("(?i)Right")]
+    Right,
+    #[regex("(?i)Substring")]
+    Substring,
+    #[regex("(?i)Len")]
+    Len,
+    #[regex("(?i)InStr")]
+    InStr,
+    #[regex("(?i)Concat")]
+    Concat,
+    #[regex("(?i)LTrim")]
+    LTrim,
+    #[regex("(?i)RTrim")]
+    RTrim,
+    #[regex("(?i)NumToStr")]
+    NumToStr,
+    #[regex("(?i)StrToNum")]
+    StrToNum,
+    #[regex("(?i)UniqueName")]
+    UniqueName,
+    #[regex("(?i)Properties")]
+    Properties,
+    #[regex("(?i)Solve_Order")]
+    SolveOrder,
+    #[regex("(?i)Format_String")]
+    FormatString,
+    #[regex("(?i)Typed")]
+    Typed,
+    #[regex("(?i)MemberToStr")]
+    MemberToStr,
+    #[regex("(?i)TupleToStr")]
+    TupleToStr,
+    #[regex("(?i)SetToStr")]
+    SetToStr,
+    #[regex("(?i)StrToMember")]
+    StrToMember,
+    #[regex("(?i)StrToTuple")]
+    StrToTuple,
+    #[regex("(?i)StrToSet")]
+    StrToSet,
+
+    // #####################################################
+    // ##  Date Functions                                 ##
+    // #####################################################
+    #[regex("(?i)Today")]
+    Today,
+    #[regex("(?i)DateDiff")]
+    DateDiff,
+    #[regex("(?i)DatePart")]
+    DatePart,
+    #[regex("(?i)DateRoll")]
+    DateRoll,
+    #[regex("(?i)DateToMember")]
+    DateToMember,
+    #[regex("(?i)DataMember")]
+    DataMember,
+    #[regex("(?i)FormatDate")]
+    FormatDate,
+    #[regex("(?i)GetFirstDate")]
+    GetFirstDate,
+    #[regex("(?i)GetLastDate")]
+    GetLastDate,
+    #[regex("(?i)JulianDate")]
+    JulianDate,
+    #[regex("(?i)UnixDate")]
+    UnixDate,
 
     // #####################################################
     // ##  Hierarchy(Role) Functions                      ##
@@ -316,6 +394,14 @@ pub enum Token {
     And,
     #[regex("(?i)Not")]
     Not,
+    #[regex("(?i)Xor")]
+    Xor,
+    #[regex("(?i)Is")]
+    Is,
+    #[regex("(?i)Null")]
+    Null,
+    #[regex("(?i)Empty")]
+    Empty,
     #[token("<")] // Less Than
     LT,
     #[token("<=")] // Less Than or Equal To
@@ -1,103 +1,753 @@
+use arc_swap::ArcSwap;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
 
 use crate::cfg::get_cfg;
-
-use crate::mdd::{Cube, Level, Member};
+use crate::mdd::{Cube, DimensionRole, HideMemberIf, Hierarchy, Level, Member};
+use crate::olapmeta_grpc_client::olapcore::MetadataSnapshotFile;
 use crate::olapmeta_grpc_client::olapmeta::UniversalOlapEntity;
-use crate::olapmeta_grpc_client::GrpcClient;
+use crate::olapmeta_grpc_client::{self as grpc, GrpcClient};
+use prost::Message;
 
-// 全局线程安全的缓存
-static LEVEL_CACHE: Lazy<Mutex<HashMap<u64, Level>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// 元数据的不可变快照，以及按常用查询方式建立的二级索引。
+///
+/// 快照加载完成后整体发布，读取方取得的是某一时刻完整一致的元数据，读取时不加锁。
+/// 这是 olap-core 唯一的元数据缓存，成员、层级、维度角色等查询都由它提供，不再逐个请求元数据服务。
+#[derive(Default)]
+pub struct MetaSnapshot {
+    // 发布时分配的版本，每发布一个新快照加一，按快照缓存的查询计划以它作为 key 的一部分
+    version: u64,
+    levels: HashMap<u64, Level>,
+    members: HashMap<u64, Member>,
+    cubes: HashMap<u64, Cube>,
+    hierarchies: HashMap<u64, Hierarchy>,
+    formula_members: HashMap<u64, UniversalOlapEntity>,
+    dimension_roles: HashMap<u64, DimensionRole>,
+    // 小写的 cube 名称 -> cube gid
+    cube_gids_by_name: HashMap<String, u64>,
+    // cube gid -> 维度角色，按元数据服务返回的顺序（Dimensions(n) 按这个顺序取）
+    dimension_roles_by_cube: HashMap<u64, Vec<DimensionRole>>,
+    // 父成员 gid -> 子成员 gid，按元数据服务返回的目录顺序，不含数据成员
+    children_by_parent: HashMap<u64, Vec<u64>>,
+    // 非叶子成员 gid -> 它的数据成员 gid
+    data_member_by_parent: HashMap<u64, u64>,
+    // level gid -> 层级上的成员 gid，按层次顺序，不含数据成员和隐藏的占位成员
+    members_by_level: HashMap<u64, Vec<u64>>,
+    // 成员 gid -> 在 members_by_level 中的位置
+    level_positions: HashMap<u64, usize>,
+    // hierarchy gid -> 层级，从上到下
+    levels_by_hierarchy: HashMap<u64, Vec<Level>>,
+    // dimension gid -> 层次，按 gid
+    hierarchies_by_dimension: HashMap<u64, Vec<Hierarchy>>,
+    // (dimension gid, 小写的成员名称) -> 成员 gid，按层级深度、再按层次顺序
+    members_by_name: HashMap<(u64, String), Vec<u64>>,
+}
 
-// 全局线程安全的缓存
-static MEMBER_CACHE: Lazy<Mutex<HashMap<u64, Member>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+impl MetaSnapshot {
+    pub fn build(
+        levels: Vec<Level>,
+        members: Vec<Member>,
+        cubes: Vec<Cube>,
+        hierarchies: Vec<Hierarchy>,
+        formula_members: Vec<UniversalOlapEntity>,
+        dimension_roles: Vec<DimensionRole>,
+    ) -> Self {
+        let mut snapshot = MetaSnapshot::default();
 
-// 全局线程安全的缓存
-static CUBE_CACHE: Lazy<Mutex<HashMap<u64, Cube>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+        for dim_role in dimension_roles {
+            snapshot
+                .dimension_roles_by_cube
+                .entry(dim_role.cube_gid)
+                .or_default()
+                .push(dim_role.clone());
+            snapshot.dimension_roles.insert(dim_role.gid, dim_role);
+        }
 
-// 全局线程安全的缓存
-static FORMULA_MEMBER_CACHE: Lazy<Mutex<HashMap<u64, UniversalOlapEntity>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
+        for level in levels {
+            snapshot
+                .levels_by_hierarchy
+                .entry(level.hierarchy_gid)
+                .or_default()
+                .push(level.clone());
+            snapshot.levels.insert(level.gid, level);
+        }
+        for levels in snapshot.levels_by_hierarchy.values_mut() {
+            levels.sort_by_key(|lv| lv.level);
+        }
 
-/// 初始化时批量拉取 level 并放入缓存
-pub async fn init() {
-    let config = get_cfg();
-    println!("< 2 > config.meta_grpc_url: {:#?}", config.meta_grpc_url);
+        for hierarchy in hierarchies {
+            snapshot
+                .hierarchies_by_dimension
+                .entry(hierarchy.dimension_gid)
+                .or_default()
+                .push(hierarchy.clone());
+            snapshot.hierarchies.insert(hierarchy.gid, hierarchy);
+        }
+        for hierarchies in snapshot.hierarchies_by_dimension.values_mut() {
+            hierarchies.sort_by_key(|h| h.gid);
+        }
+
+        for member in &members {
+            if member.data_member {
+                snapshot
+                    .data_member_by_parent
+                    .insert(member.parent_gid, member.gid);
+            } else {
+                snapshot
+                    .children_by_parent
+                    .entry(member.parent_gid)
+                    .or_default()
+                    .push(member.gid);
+            }
+            if let Some(level) = snapshot.levels.get(&member.level_gid) {
+                snapshot
+                    .members_by_name
+                    .entry((level.dimension_gid, fold_name(&member.name)))
+                    .or_default()
+                    .push(member.gid);
+            }
+        }
+        snapshot.members = members.into_iter().map(|m| (m.gid, m)).collect();
+
+        // 从顶层成员开始按 children_by_parent 的目录顺序深度优先遍历得到的层次顺序，
+        // 同一层级上的成员先按祖先的顺序、再按同一父节点下的目录顺序排列
+        let members = &snapshot.members;
+        let mut roots: Vec<u64> = snapshot
+            .children_by_parent
+            .keys()
+            .filter(|parent_gid| **parent_gid != 0 && !members.contains_key(parent_gid))
+            .copied()
+            .collect();
+        roots.sort();
+        roots.insert(0, 0);
+        let mut hierarchy_order: Vec<u64> = Vec::with_capacity(members.len());
+        for root in roots {
+            let mut stack: Vec<u64> = snapshot
+                .children_by_parent
+                .get(&root)
+                .map_or(vec![], |children| children.iter().rev().copied().collect());
+            while let Some(gid) = stack.pop() {
+                hierarchy_order.push(gid);
+                if let Some(children) = snapshot.children_by_parent.get(&gid) {
+                    stack.extend(children.iter().rev());
+                }
+            }
+        }
+        let order_positions: HashMap<u64, usize> = hierarchy_order
+            .iter()
+            .enumerate()
+            .map(|(pos, gid)| (*gid, pos))
+            .collect();
+
+        for gids in snapshot.members_by_name.values_mut() {
+            gids.sort_by_key(|gid| (members[gid].level, order_positions.get(gid).copied()));
+        }
+
+        let mut members_by_level: HashMap<u64, Vec<u64>> = HashMap::new();
+        for member in hierarchy_order.iter().map(|gid| &members[gid]) {
+            let hide_member_if = snapshot
+                .levels
+                .get(&member.level_gid)
+                .map_or(HideMemberIf::Never, |lv| lv.hide_member_if);
+            if hide_member_if != HideMemberIf::Never && member.parent_gid != 0 {
+                let siblings = snapshot
+                    .children_by_parent
+                    .get(&member.parent_gid)
+                    .map_or(0, |children| children.len());
+                if hide_member_if.hides(member, members.get(&member.parent_gid), siblings) {
+                    continue;
+                }
+            }
+            members_by_level
+                .entry(member.level_gid)
+                .or_default()
+                .push(member.gid);
+        }
+        for gids in members_by_level.values() {
+            for (pos, gid) in gids.iter().enumerate() {
+                snapshot.level_positions.insert(*gid, pos);
+            }
+        }
+        snapshot.members_by_level = members_by_level;
+
+        snapshot.cube_gids_by_name = cubes.iter().map(|c| (fold_name(&c.name), c.gid)).collect();
+        snapshot.cubes = cubes.into_iter().map(|c| (c.gid, c)).collect();
+        snapshot.formula_members = formula_members.into_iter().map(|fm| (fm.gid, fm)).collect();
+
+        snapshot
+    }
+
+    fn member(&self, gid: u64) -> &Member {
+        match self.members.get(&gid) {
+            Some(member) => member,
+            None => panic!("Member not found for gid {}", gid),
+        }
+    }
+
+    fn level(&self, gid: u64) -> &Level {
+        match self.levels.get(&gid) {
+            Some(level) => level,
+            None => panic!("Level not found for gid {}", gid),
+        }
+    }
+
+    fn members_at_level(&self, level_gid: u64) -> &[u64] {
+        self.members_by_level
+            .get(&level_gid)
+            .map_or(&[], |gids| gids.as_slice())
+    }
+
+    /// 由元数据服务返回的原始元数据（或从快照文件读出的相同内容）建立快照。
+    fn from_metadata(metadata: MetadataSnapshotFile) -> Self {
+        let levels: Vec<Level> = metadata
+            .levels
+            .into_iter()
+            .map(grpc::grpc_to_olap_level)
+            .collect();
+        let dimension_roles: Vec<DimensionRole> = metadata
+            .dimension_roles
+            .into_iter()
+            .map(grpc::grpc_to_olap_dimension_role)
+            .collect();
+        let mut hierarchies: Vec<Hierarchy> = metadata
+            .hierarchies
+            .into_iter()
+            .map(grpc::grpc_to_olap_hierarchy)
+            .collect();
+        if hierarchies.is_empty() {
+            hierarchies = default_hierarchies(&levels, &dimension_roles);
+        }
+
+        MetaSnapshot::build(
+            levels,
+            metadata
+                .members
+                .into_iter()
+                .map(grpc::grpc_to_olap_member)
+                .collect(),
+            metadata
+                .cubes
+                .into_iter()
+                .map(grpc::grpc_to_olap_cube)
+                .collect(),
+            hierarchies,
+            metadata.formula_members,
+            dimension_roles,
+        )
+    }
+
+    /// 与另一个快照的元数据是否完全相同（索引由元数据生成，不需要比较）。
+    fn same_content(&self, other: &MetaSnapshot) -> bool {
+        self.levels == other.levels
+            && self.members == other.members
+            && self.cubes == other.cubes
+            && self.hierarchies == other.hierarchies
+            && self.formula_members == other.formula_members
+            && self.dimension_roles == other.dimension_roles
+    }
+
+    fn members_by_name(&self, dimension_gid: u64, name: &str) -> &[u64] {
+        self.members_by_name
+            .get(&(dimension_gid, fold_name(name)))
+            .map_or(&[], |gids| gids.as_slice())
+    }
+}
+
+/// 不支持 GetAllHierarchies 的旧版本元数据服务没有返回层次，此时每个维度只有一个默认层次：
+/// 由层级和维度角色上的 hierarchy gid 得到，名称与维度角色相同，默认成员为顶层成员。
+fn default_hierarchies(levels: &[Level], dimension_roles: &[DimensionRole]) -> Vec<Hierarchy> {
+    let mut hierarchies: HashMap<u64, Hierarchy> = HashMap::new();
+    let hierarchy_dims = levels
+        .iter()
+        .map(|lv| (lv.hierarchy_gid, lv.dimension_gid))
+        .chain(
+            dimension_roles
+                .iter()
+                .map(|dr| (dr.default_hierarchy_gid, dr.dimension_gid)),
+        );
+    for (hierarchy_gid, dimension_gid) in hierarchy_dims {
+        if hierarchy_gid == 0 {
+            continue;
+        }
+        hierarchies
+            .entry(hierarchy_gid)
+            .or_insert_with(|| Hierarchy {
+                gid: hierarchy_gid,
+                name: dimension_roles
+                    .iter()
+                    .find(|dr| dr.dimension_gid == dimension_gid)
+                    .map_or_else(String::new, |dr| dr.name.clone()),
+                dimension_gid,
+                default_member_gid: 0,
+                parent_child: false,
+            });
+    }
+    hierarchies.into_values().collect()
+}
+
+// 当前发布的元数据快照，加载或重新加载完成后整体替换
+static SNAPSHOT: Lazy<ArcSwap<MetaSnapshot>> =
+    Lazy::new(|| ArcSwap::from_pointee(MetaSnapshot::default()));
+
+tokio::task_local! {
+    // 查询开始时固定的快照，查询执行期间重新加载元数据不影响这个查询
+    static PINNED_SNAPSHOT: Arc<MetaSnapshot>;
+}
+
+// 同一时间只进行一次重新加载
+static RELOAD_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+const WATCH_RETRY_DELAY: Duration = Duration::from_secs(5);
+const REFRESH_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+// 快照文件的格式版本，文件中的版本不一致时忽略这个文件，从元数据服务加载
+const SNAPSHOT_FILE_FORMAT_VERSION: u32 = 1;
+
+/// 当前任务使用的快照：在 with_pinned_snapshot 中执行时为查询开始时的快照，否则为最新发布的快照。
+fn current() -> Arc<MetaSnapshot> {
+    PINNED_SNAPSHOT
+        .try_with(Arc::clone)
+        .unwrap_or_else(|_| SNAPSHOT.load_full())
+}
+
+/// 当前任务使用的快照的版本。
+pub fn snapshot_version() -> u64 {
+    current().version
+}
+
+/// 在当前发布的快照上执行一个查询，执行期间所有元数据查询都使用这个快照。
+pub async fn with_pinned_snapshot<F: Future>(fut: F) -> F::Output {
+    PINNED_SNAPSHOT.scope(SNAPSHOT.load_full(), fut).await
+}
+
+/// 在给定的快照上执行，单元测试用它代替从元数据服务加载的元数据。
+#[cfg(test)]
+pub async fn with_snapshot<F: Future>(snapshot: MetaSnapshot, fut: F) -> F::Output {
+    PINNED_SNAPSHOT.scope(Arc::new(snapshot), fut).await
+}
 
-    let mut grpc_cli = GrpcClient::new(config.meta_grpc_url)
+/// 从元数据服务批量拉取 level、member、cube、hierarchy、计算成员和维度角色。
+async fn fetch_metadata() -> Result<MetadataSnapshotFile, String> {
+    let mut grpc_cli = GrpcClient::get_cli().await;
+    let mut metadata = grpc_cli
+        .get_all_metadata()
         .await
-        .expect("Failed to create client");
+        .map_err(|e| e.to_string())?;
+    metadata.format_version = SNAPSHOT_FILE_FORMAT_VERSION;
+    Ok(metadata)
+}
+
+/// 配置了快照文件时先编码元数据，建立快照后原始元数据不再保留。
+fn encode_for_snapshot_file(metadata: &MetadataSnapshotFile) -> Option<Vec<u8>> {
+    get_cfg()
+        .meta_snapshot_path
+        .as_ref()
+        .map(|_| metadata.encode_to_vec())
+}
 
-    // levels 的类型是 Vec<Level>
-    let levels = grpc_cli.get_all_levels().await.unwrap();
+/// 读取本地的快照文件，文件不存在、无法解码或格式版本不一致时返回 None。
+fn read_snapshot_file() -> Option<MetaSnapshot> {
+    let path = get_cfg().meta_snapshot_path.as_ref()?;
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            println!(
+                "[meta-004] Failed to read metadata snapshot file {}: {}",
+                path, e
+            );
+            return None;
+        }
+    };
+    let metadata = match MetadataSnapshotFile::decode(bytes.as_slice()) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            println!(
+                "[meta-004] Failed to decode metadata snapshot file {}: {}",
+                path, e
+            );
+            return None;
+        }
+    };
+    if metadata.format_version != SNAPSHOT_FILE_FORMAT_VERSION {
+        println!(
+            "[meta-004] Ignoring metadata snapshot file {} with format version {}.",
+            path, metadata.format_version
+        );
+        return None;
+    }
+    println!("Loaded metadata snapshot file {}.", path);
+    Some(MetaSnapshot::from_metadata(metadata))
+}
 
-    let mut cache = LEVEL_CACHE.lock().unwrap();
-    for level in levels {
-        // println!("OO>>>>>>>>>>>>>> level: {:#?}", level);
-        cache.insert(level.gid, level);
+/// 写入快照文件：先写临时文件再改名，写入过程中进程退出不会留下不完整的快照文件。
+async fn write_snapshot_file(bytes: Option<Vec<u8>>) {
+    let (Some(path), Some(bytes)) = (get_cfg().meta_snapshot_path.clone(), bytes) else {
+        return;
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        let tmp_path = format!("{}.tmp", path);
+        std::fs::write(&tmp_path, &bytes)
+            .and_then(|_| std::fs::rename(&tmp_path, &path))
+            .map_err(|e| format!("{}: {}", path, e))
+    })
+    .await;
+    if let Ok(Err(msg)) = result {
+        println!("[meta-005] Failed to write metadata snapshot file {}", msg);
     }
+}
 
-    // members 的类型是 Vec<Member>
-    let members = grpc_cli.get_all_members().await.unwrap();
+/// 发布新的快照并递增元数据版本，依赖旧元数据的查询计划和结果缓存随之失效。
+/// 正在执行的查询继续使用它们开始时的快照。
+fn publish(snapshot: MetaSnapshot) {
+    println!(
+        "Successfully loaded {} members into the metadata snapshot.",
+        snapshot.members.len()
+    );
+    let mut snapshot = snapshot;
+    snapshot.version = SNAPSHOT.load().version + 1;
+    SNAPSHOT.store(Arc::new(snapshot));
+    crate::result_cache::bump_meta_version();
+}
 
-    let mut cache = MEMBER_CACHE.lock().unwrap();
-    for member in members {
-        // println!("OO>>>>>>>>>>>>>> member: {:#?}", member);
-        cache.insert(member.gid, member);
+/// 启动时加载元数据。
+///
+/// 有可用的快照文件时直接从文件加载，不等待元数据服务，随后在后台从元数据服务刷新；
+/// 否则从元数据服务加载，连接不上时一直重试。
+pub async fn init() {
+    if let Some(snapshot) = read_snapshot_file() {
+        publish(snapshot);
+        tokio::spawn(refresh_until_loaded());
+        return;
     }
 
-    // cubes 的类型是 Vec<Cube>
-    let cubes = grpc_cli.get_all_cubes().await.unwrap();
+    let metadata = loop {
+        match fetch_metadata().await {
+            Ok(metadata) => break metadata,
+            Err(e) => {
+                println!(
+                    "[meta-001] Failed to load metadata: {}. Retrying in {:?}...",
+                    e, REFRESH_RETRY_DELAY
+                );
+                sleep(REFRESH_RETRY_DELAY).await;
+            }
+        }
+    };
+    let bytes = encode_for_snapshot_file(&metadata);
+    publish(MetaSnapshot::from_metadata(metadata));
+    write_snapshot_file(bytes).await;
+}
 
-    let mut cache = CUBE_CACHE.lock().unwrap();
-    for cube in cubes {
-        // println!("OO>>>>>>>>>>>>>> cube: {:#?}", cube);
-        cache.insert(cube.gid, cube);
+/// 从快照文件启动后，在后台从元数据服务重新加载，失败时等待一段时间重试，直到成功一次。
+async fn refresh_until_loaded() {
+    while let Err(msg) = reload().await {
+        println!("{}", msg);
+        sleep(REFRESH_RETRY_DELAY).await;
     }
+}
+
+/// 重新加载元数据，返回元数据是否有变化。没有变化时不替换快照，缓存保持有效。
+pub async fn reload() -> Result<bool, String> {
+    let _guard = RELOAD_LOCK.lock().await;
 
-    let formula_members = grpc_cli.get_all_formula_members().await.unwrap();
-    let mut cache = FORMULA_MEMBER_CACHE.lock().unwrap();
-    for fm in formula_members {
-        // println!(">>>>>>>>>>>>>>>> A Formula Member >>>>>>>>>>>>>>>>>>>>>>>>>>>>\n{:#?}", fm);
-        cache.insert(fm.gid, fm);
+    let metadata = fetch_metadata()
+        .await
+        .map_err(|e| format!("[meta-002] Failed to reload metadata: {}", e))?;
+    let bytes = encode_for_snapshot_file(&metadata);
+    let snapshot = MetaSnapshot::from_metadata(metadata);
+    if snapshot.same_content(&SNAPSHOT.load()) {
+        return Ok(false);
     }
+    publish(snapshot);
+    write_snapshot_file(bytes).await;
+    Ok(true)
 }
 
-/// 多线程安全地根据 gid 获取 level
-pub fn get_level_by_gid(gid: u64) -> Level {
-    let cache = LEVEL_CACHE.lock().unwrap();
-    match cache.get(&gid) {
-        Some(level) => level.clone(),
-        None => panic!("Level not found for gid {}", gid),
+/// 按配置启动后台的元数据热加载：定期重新加载，以及订阅元数据服务的变化通知。
+pub fn spawn_reloaders() {
+    let config = get_cfg();
+
+    if let Some(secs) = config.meta_reload_interval_secs.filter(|secs| *secs > 0) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(secs));
+            // 第一次 tick 立即返回，启动时已经加载过元数据
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(msg) = reload().await {
+                    println!("{}", msg);
+                }
+            }
+        });
+    }
+
+    if config.meta_watch_changes.unwrap_or(false) {
+        tokio::spawn(watch_metadata_changes());
     }
 }
 
-/// 多线程安全地根据 gid 获取 member
-pub fn get_member_by_gid(gid: u64) -> Member {
-    let cache = MEMBER_CACHE.lock().unwrap();
-    match cache.get(&gid) {
-        Some(member) => member.clone(),
-        None => panic!("Member not found for gid {}", gid),
+/// 订阅元数据服务的变化通知，每收到一个通知重新加载一次。
+/// 连接断开后等待一段时间重新订阅（重新订阅前先加载一次，以免漏掉断开期间的变化），
+/// 元数据服务不支持订阅时停止。
+async fn watch_metadata_changes() {
+    let mut resubscribe = false;
+    loop {
+        if resubscribe {
+            sleep(WATCH_RETRY_DELAY).await;
+            if let Err(msg) = reload().await {
+                println!("{}", msg);
+            }
+        }
+        resubscribe = true;
+
+        let mut grpc_cli = GrpcClient::get_cli().await;
+        let mut stream = match grpc_cli.watch_metadata_changes().await {
+            Ok(stream) => stream,
+            Err(status) if status.code() == tonic::Code::Unimplemented => {
+                println!("[meta-003] The metadata service does not support change notifications.");
+                return;
+            }
+            Err(status) => {
+                println!("[meta-003] Failed to watch metadata changes: {}", status);
+                continue;
+            }
+        };
+
+        loop {
+            match stream.message().await {
+                Ok(Some(_event)) => {
+                    if let Err(msg) = reload().await {
+                        println!("{}", msg);
+                    }
+                }
+                Ok(None) => break,
+                Err(status) => {
+                    println!("[meta-003] Metadata change stream interrupted: {}", status);
+                    break;
+                }
+            }
+        }
     }
 }
 
-/// 多线程安全地根据 gid 获取 cube
+/// 名称不区分大小写：cube、维度角色、层次、层级、成员和属性的名称都按这个规则折叠后比较。
+pub fn fold_name(name: &str) -> String {
+    name.to_lowercase()
+}
+
+pub fn names_match(a: &str, b: &str) -> bool {
+    a == b || fold_name(a) == fold_name(b)
+}
+
+/// 根据 gid 获取 level
+pub fn get_level_by_gid(gid: u64) -> Level {
+    current().level(gid).clone()
+}
+
+/// 根据 gid 获取 member
+pub fn get_member_by_gid(gid: u64) -> Member {
+    current().member(gid).clone()
+}
+
+/// 根据 gid 获取 member，不存在时返回 None
+pub fn find_member_by_gid(gid: u64) -> Option<Member> {
+    current().members.get(&gid).cloned()
+}
+
+/// 成员所在的维度，成员或它的层级不存在时返回 None
+pub fn member_dimension_gid(member: &Member) -> Option<u64> {
+    current()
+        .levels
+        .get(&member.level_gid)
+        .map(|lv| lv.dimension_gid)
+}
+
+/// 根据 gid 获取 cube
 pub fn get_cube_by_gid(gid: u64) -> Cube {
-    let cache = CUBE_CACHE.lock().unwrap();
-    match cache.get(&gid) {
+    match current().cubes.get(&gid) {
         Some(cube) => cube.clone(),
         None => panic!("Cube not found for gid {}", gid),
     }
 }
 
-pub fn get_hierarchy_level(hierarchy_gid: u64, level_val: u32) -> Level {
-    let cache = LEVEL_CACHE.lock().unwrap();
-    for level in cache.values() {
-        if level.hierarchy_gid == hierarchy_gid && level.level == level_val {
-            return level.clone();
+/// 根据 gid 获取 cube，不存在时返回 None
+pub fn find_cube_by_gid(gid: u64) -> Option<Cube> {
+    current().cubes.get(&gid).cloned()
+}
+
+/// 根据名称（不区分大小写）获取 cube
+pub fn find_cube_by_name(name: &str) -> Option<Cube> {
+    let snapshot = current();
+    let gid = snapshot.cube_gids_by_name.get(&fold_name(name))?;
+    snapshot.cubes.get(gid).cloned()
+}
+
+/// 根据 gid 获取维度角色
+pub fn get_dimension_role_by_gid(gid: u64) -> DimensionRole {
+    match current().dimension_roles.get(&gid) {
+        Some(dim_role) => dim_role.clone(),
+        None => panic!("DimensionRole not found for gid {}", gid),
+    }
+}
+
+/// 返回 cube 的所有维度角色。
+pub fn get_dimension_roles_by_cube_gid(cube_gid: u64) -> Vec<DimensionRole> {
+    current()
+        .dimension_roles_by_cube
+        .get(&cube_gid)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// 根据名称（不区分大小写）获取 cube 中的维度角色
+pub fn find_dimension_role_by_name(cube_gid: u64, name: &str) -> Option<DimensionRole> {
+    current()
+        .dimension_roles_by_cube
+        .get(&cube_gid)?
+        .iter()
+        .find(|dr| names_match(&dr.name, name))
+        .cloned()
+}
+
+/// 根据 gid 获取 hierarchy
+pub fn get_hierarchy_by_gid(gid: u64) -> Hierarchy {
+    match current().hierarchies.get(&gid) {
+        Some(hierarchy) => hierarchy.clone(),
+        None => panic!("Hierarchy not found for gid {}", gid),
+    }
+}
+
+/// 返回层次的默认成员：优先使用层次上配置的 default_member_gid，
+/// 未配置时使用层次顶层（level 0）的第一个成员。
+pub fn get_hierarchy_default_member(hierarchy_gid: u64) -> Member {
+    let hierarchy = get_hierarchy_by_gid(hierarchy_gid);
+    if hierarchy.default_member_gid != 0 {
+        return get_member_by_gid(hierarchy.default_member_gid);
+    }
+
+    let top_level = get_hierarchy_level(hierarchy_gid, 0);
+    match get_members_at_level(top_level.gid).into_iter().next() {
+        Some(member) => member,
+        None => panic!("Hierarchy {} has no top member", hierarchy_gid),
+    }
+}
+
+/// 返回维度角色的默认成员，即维度默认层次的默认成员。
+/// 维度角色没有指定默认层次时使用维度上 gid 最小的层次。
+pub fn get_dimension_default_member(dim_role: &DimensionRole) -> Member {
+    let hierarchy_gid = if dim_role.default_hierarchy_gid != 0 {
+        dim_role.default_hierarchy_gid
+    } else {
+        match get_hierarchies_by_dimension(dim_role.dimension_gid).first() {
+            Some(hierarchy) => hierarchy.gid,
+            None => panic!(
+                "[dm-002] Dimension {} of DimensionRole {} has no hierarchy",
+                dim_role.dimension_gid, dim_role.gid
+            ),
         }
+    };
+    get_hierarchy_default_member(hierarchy_gid)
+}
+
+/// 返回维度上的所有层次。
+pub fn get_hierarchies_by_dimension(dimension_gid: u64) -> Vec<Hierarchy> {
+    current()
+        .hierarchies_by_dimension
+        .get(&dimension_gid)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// 返回层次上的所有层级，按 level 从上到下排列。
+pub fn get_hierarchy_levels(hierarchy_gid: u64) -> Vec<Level> {
+    current()
+        .levels_by_hierarchy
+        .get(&hierarchy_gid)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// 返回层次上名称匹配（不区分大小写）的所有成员，按层级深度、再按层次顺序排列。
+pub fn get_hierarchy_members_by_name(hierarchy_gid: u64, name: &str) -> Vec<Member> {
+    let snapshot = current();
+    let Some(hierarchy) = snapshot.hierarchies.get(&hierarchy_gid) else {
+        return vec![];
+    };
+    snapshot
+        .members_by_name(hierarchy.dimension_gid, name)
+        .iter()
+        .map(|gid| snapshot.member(*gid))
+        .filter(|m| {
+            snapshot
+                .levels
+                .get(&m.level_gid)
+                .is_some_and(|lv| lv.hierarchy_gid == hierarchy_gid)
+        })
+        .cloned()
+        .collect()
+}
+
+/// 返回层级上名称匹配（不区分大小写）的所有成员，按层次顺序排列。
+pub fn get_level_members_by_name(level_gid: u64, name: &str) -> Vec<Member> {
+    let snapshot = current();
+    let Some(level) = snapshot.levels.get(&level_gid) else {
+        return vec![];
+    };
+    snapshot
+        .members_by_name(level.dimension_gid, name)
+        .iter()
+        .map(|gid| snapshot.member(*gid))
+        .filter(|m| m.level_gid == level_gid)
+        .cloned()
+        .collect()
+}
+
+/// 返回 `ancestor_gid` 的后代中名称匹配（不区分大小写）的所有成员，按层级深度、再按层次顺序排列。
+pub fn get_descendants_by_name(ancestor_gid: u64, name: &str) -> Vec<Member> {
+    let snapshot = current();
+    let Some(ancestor) = snapshot.members.get(&ancestor_gid) else {
+        return vec![];
+    };
+    let Some(level) = snapshot.levels.get(&ancestor.level_gid) else {
+        return vec![];
+    };
+    snapshot
+        .members_by_name(level.dimension_gid, name)
+        .iter()
+        .map(|gid| snapshot.member(*gid))
+        .filter(|m| m.gid != ancestor_gid && m.full_path.contains(&ancestor_gid))
+        .cloned()
+        .collect()
+}
+
+/// 按层级序号取得层次上的层级。
+///
+/// 父子层次的层级由元数据服务按深度生成（Level 01、Level 02 ...），各分支的深度可以不同，
+/// 请求的深度超过最深的分支时没有对应的层级。
+pub fn get_hierarchy_level(hierarchy_gid: u64, level_val: u32) -> Level {
+    let snapshot = current();
+    let levels = snapshot
+        .levels_by_hierarchy
+        .get(&hierarchy_gid)
+        .map_or(&[][..], |levels| levels.as_slice());
+    if let Some(level) = levels.iter().find(|lv| lv.level == level_val) {
+        return level.clone();
+    }
+
+    let parent_child = snapshot
+        .hierarchies
+        .get(&hierarchy_gid)
+        .is_some_and(|h| h.parent_child);
+    if parent_child {
+        panic!(
+            "Parent-child hierarchy {} has no member at depth {}, its deepest level is {}",
+            hierarchy_gid,
+            level_val,
+            levels.last().map_or(0, |lv| lv.level)
+        );
     }
     panic!(
         "Level not found for hierarchy_gid = {} and level = {}",
@@ -106,15 +756,31 @@ pub fn get_hierarchy_level(hierarchy_gid: u64, level_val: u32) -> Level {
 }
 
 pub fn mdx_formula_members_fragment(cube: &Cube) -> String {
-    let cache = FORMULA_MEMBER_CACHE.lock().unwrap();
+    let snapshot = current();
 
-    let fragments: Vec<String> = cache
+    let fragments: Vec<String> = snapshot
+        .formula_members
         .values()
         .filter(|fm| fm.cube_gid == cube.gid)
         .map(|fm| {
+            let mut props = String::new();
+            if fm.solve_order != 0 {
+                props.push_str(&format!(", SOLVE_ORDER = {}", fm.solve_order));
+            }
+            if !fm.format_string.is_empty() {
+                props.push_str(&format!(
+                    ", FORMAT_STRING = \"{}\"",
+                    fm.format_string.replace('"', "\"\"")
+                ));
+            }
             format!(
-                "Member &{}.&{}.&{}[{}] as {}",
-                fm.dimension_role_gid, fm.mount_point_gid, fm.gid, fm.name, fm.exp
+                "Member &{}.&{}.&{}[{}] as {}{}",
+                fm.dimension_role_gid,
+                fm.mount_point_gid,
+                fm.gid,
+                fm.name.replace(']', "]]"),
+                fm.exp,
+                props
             )
         })
         .collect();
@@ -122,41 +788,78 @@ pub fn mdx_formula_members_fragment(cube: &Cube) -> String {
     fragments.join(",\n")
 }
 
-/// 获取指定 level_gid 上的所有成员，按 gid 排序返回。
+/// 获取指定 level_gid 上的所有成员，按层次顺序返回。
 ///
-/// 从 MEMBER_CACHE 中筛选出 level_gid 相同的所有成员，
-/// 按 gid 排序后返回。这样可以保证同一层级的成员有确定的顺序。
+/// 同一层级的成员按 full_path 排序（full_path 相同时按 gid），
+/// 即先按祖先的顺序、再按同一父节点下的顺序排列。这样同一层级的成员有确定的顺序，
+/// 并且跨越父节点时（例如 1 月的最后一天与 2 月的第一天）依然相邻。
+/// 数据成员和按 HideMemberIf 隐藏的占位成员不包含在结果中。
 pub fn get_members_at_level(level_gid: u64) -> Vec<Member> {
-    let cache = MEMBER_CACHE.lock().unwrap();
-    let mut members: Vec<Member> = cache
-        .values()
-        .filter(|m| m.level_gid == level_gid)
-        .cloned()
-        .collect();
-    
-    // 按 gid 排序，确保顺序稳定
-    members.sort_by_key(|m| m.gid);
-    members
+    let snapshot = current();
+    snapshot
+        .members_at_level(level_gid)
+        .iter()
+        .map(|gid| snapshot.member(*gid).clone())
+        .collect()
+}
+
+/// 返回与 `member_gid` 同层级、相距 `offset` 个位置的成员（正数向后，负数向前），
+/// 可以跨越父节点。超出层级范围时返回 None。
+pub fn get_member_on_level_by_offset(member_gid: u64, offset: i64) -> Option<Member> {
+    let snapshot = current();
+
+    // 空成员、数据成员和隐藏成员不在层级的成员列表中，没有同层级的相邻成员
+    let pos = *snapshot.level_positions.get(&member_gid)?;
+    let member = snapshot.member(member_gid);
+    let members_at_level = snapshot.members_at_level(member.level_gid);
+
+    let target_pos = pos as i64 + offset;
+    if target_pos < 0 || target_pos >= members_at_level.len() as i64 {
+        return None;
+    }
+    Some(
+        snapshot
+            .member(members_at_level[target_pos as usize])
+            .clone(),
+    )
+}
+
+/// 沿 parent_gid 向上移动 `distance` 层，返回对应的祖先成员。超过根节点时返回 None。
+pub fn get_member_ancestor_by_distance(member_gid: u64, distance: u32) -> Option<Member> {
+    let snapshot = current();
+    let mut member = snapshot.member(member_gid);
+    for _ in 0..distance {
+        if member.parent_gid == 0 {
+            return None;
+        }
+        member = snapshot.member(member.parent_gid);
+    }
+    Some(member.clone())
 }
 
 /// 返回给定 `member_gid` 在指定 `level_gid` 上的祖先 Member。
 ///
-/// 实现策略：使用内存缓存 `MEMBER_CACHE`，通过 member.parent_gid 向上遍历，
+/// 实现策略：在同一个元数据快照中通过 member.parent_gid 向上遍历，
 /// 直到找到 level_gid 相等的 member 并返回它。如果遍历到 root（parent_gid == 0）仍未找到，
 /// 则 panic（调用方应保证请求的 level 是该 member 的上级层次之一）。
+///
+/// 不规则层次中成员的父成员可能跳过若干层级，此时请求的层级上没有真实的祖先，
+/// 返回跳过该层级的最近祖先（即该层级上隐藏的占位成员所代表的成员）。
 pub fn get_member_ancestor_on_level(member_gid: u64, level_gid: u64) -> Member {
-    // Start from the provided member and walk up using cached members.
+    let snapshot = current();
+    let target_level = snapshot.level(level_gid).level;
+
+    // Start from the provided member and walk up using the snapshot.
     let mut cur_gid = member_gid;
 
     loop {
-        let cache = MEMBER_CACHE.lock().unwrap();
-        let member = match cache.get(&cur_gid) {
-            Some(m) => m.clone(),
+        let member = match snapshot.members.get(&cur_gid) {
+            Some(m) => m,
             None => panic!("Member not found for gid {} when searching ancestor", cur_gid),
         };
 
-        if member.level_gid == level_gid {
-            return member;
+        if member.level_gid == level_gid || (cur_gid != member_gid && member.level < target_level) {
+            return member.clone();
         }
 
         if member.parent_gid == 0 {
@@ -193,34 +896,78 @@ pub fn get_member_ancestor_on_level(member_gid: u64, level_gid: u64) -> Member {
 /// 在祖先节点上平移并取得对应位置的成员。
 ///
 /// 参数：
-/// - `grpc_cli`: 已初始化的 `GrpcClient`（可变引用），用于按顺序获取某个 member 的子成员列表（以保留目录顺序）。
 /// - `ancestor_gid`: 被视为祖先的 member 的 gid（必须是 `member_gid` 的某一上层）。
 /// - `member_gid`: 原始的后代 member gid。
 /// - `offset`: 偏移量，正数表示向前（索引减小）移动，负数表示向后（索引增大）移动。
 ///
 /// 实现：
 /// 1. 检查 `ancestor_gid` 是否确实出现在 `member_gid` 的 `full_path` 中（否则 panic）。
-/// 2. 从 `member_gid` 向上遍历到 `ancestor_gid`，在每一层通过 `grpc_cli.get_child_members_by_gid(parent_gid)`
-///    获取子成员列表以确定当前成员在父节点下的顺序索引，记录下这些索引（从祖先的子层开始到目标成员的路径）。
-/// 3. 在祖先的父节点下获取祖先的兄弟列表，按 `offset` 平移得到新的祖先。
-/// 4. 从新的祖先按步骤 2 中记录的索引逐层向下查找对应的成员并返回。
+/// 2. 通过 `member_position_under_ancestor` 记录 member 相对于祖先的位置描述列表。
+/// 3. 在祖先所在层级上按 `offset` 平移得到新的祖先（与 Lag 相同，可以跨越父节点，例如 2024 年 1 月向前 1 个月为 2023 年 12 月）。
+/// 4. 从新的祖先按位置描述列表逐层向下查找对应的成员。
+///    某一层的子成员数量不足时取最后一个子成员（例如 3 月 31 日平移一个月得到 2 月 28/29 日）。
 ///
-/// 注意：该函数在未找到预期成员或索引越界时会 panic（与项目中其他缓存/辅助函数风格保持一致）。
-pub async fn shift_ancestor_and_find_member(
-    grpc_cli: &mut GrpcClient,
+/// 平移后的祖先超出层级范围，或新的祖先下没有对应深度的后代时返回 None。
+pub fn shift_ancestor_and_find_member(
     ancestor_gid: u64,
     member_gid: u64,
     offset: i32,
-) -> Member {
+) -> Option<Member> {
     // 验证 ancestor 是 member 的祖先（使用 full_path）
     let member = get_member_by_gid(member_gid);
-    if !member.full_path.contains(&ancestor_gid) {
+    if ancestor_gid != member_gid && !member.full_path.contains(&ancestor_gid) {
         panic!(
             "Ancestor gid {} is not an ancestor of member {}",
             ancestor_gid, member_gid
         );
     }
 
+    let indices = member_position_under_ancestor(ancestor_gid, member_gid);
+
+    // offset: 按调用约定，正数表示向前移动（索引减小），负数表示向后移动（索引增大）——与 MDX ParallelPeriod 的常见语义保持一致
+    let target_ancestor = get_member_on_level_by_offset(ancestor_gid, -(offset as i64))?;
+
+    // 从 target_ancestor 向下按位置描述列表查找最终成员，索引越界时取最后一个子成员
+    let mut cur = target_ancestor;
+    for idx in indices.iter() {
+        let children = get_ordered_children(cur.gid);
+        let child = children.get(*idx).or_else(|| children.last())?;
+        cur = child.clone();
+    }
+    Some(cur)
+}
+
+/// 返回 `member_gid` 在 `level_gid` 层级上的第一个（`last` 为 false）或最后一个（`last` 为 true）后代成员，
+/// 用于 OpeningPeriod / ClosingPeriod。
+///
+/// member 本身位于该层级时返回 member；层级在 member 之上，或在到达该层级前遇到叶子成员时返回 None。
+pub fn find_edge_descendant_on_level(
+    member_gid: u64,
+    level_gid: u64,
+    last: bool,
+) -> Option<Member> {
+    let target_level = get_level_by_gid(level_gid);
+    let mut cur = get_member_by_gid(member_gid);
+    while cur.level_gid != level_gid {
+        if cur.level >= target_level.level {
+            return None;
+        }
+        let children = get_ordered_children(cur.gid);
+        let child = if last {
+            children.last()
+        } else {
+            children.first()
+        }?;
+        cur = child.clone();
+    }
+    Some(cur)
+}
+
+/// 计算 `member_gid` 相对于其祖先 `ancestor_gid` 的位置描述列表（从祖先的子层开始向下，每层为在父节点下的索引）。
+///
+/// 例如上面注释中的 grandchild3 相对于 ancestor_member 的位置为 `[1, 0]`。
+/// 子成员的顺序通过 `get_ordered_children` 获取，以保留目录顺序。
+pub fn member_position_under_ancestor(ancestor_gid: u64, member_gid: u64) -> Vec<usize> {
     // 从 member 向上到 ancestor，记录每一级在其父节点下的索引（从下向上）
     let mut indices_rev: Vec<usize> = Vec::new();
     let mut cur_gid = member_gid;
@@ -230,16 +977,9 @@ pub async fn shift_ancestor_and_find_member(
         let parent_gid = cur.parent_gid;
 
         // 获取 parent 的子成员（有序）
-        let children = grpc_cli.get_child_members_by_gid(parent_gid).await.unwrap();
+        let children = get_ordered_children(parent_gid);
         // 找到 cur_gid 在 children 中的索引
-        let mut found_idx: Option<usize> = None;
-        for (i, ch) in children.iter().enumerate() {
-            if ch.gid == cur_gid {
-                found_idx = Some(i);
-                break;
-            }
-        }
-        let idx = match found_idx {
+        let idx = match children.iter().position(|ch| ch.gid == cur_gid) {
             Some(i) => i,
             None => panic!("Failed to locate member {} under parent {}", cur_gid, parent_gid),
         };
@@ -253,40 +993,197 @@ pub async fn shift_ancestor_and_find_member(
 
     // 现在 indices_rev 列表包含从 member 向上至 ancestor 的索引（child index），需要反转为从 ancestor 向下
     indices_rev.reverse();
+    indices_rev
+}
 
-    // 在祖先的父节点下找到祖先的兄弟并应用 offset
-    let ancestor = get_member_by_gid(ancestor_gid);
-    let ancestor_parent_gid = ancestor.parent_gid;
-    let siblings = grpc_cli.get_child_members_by_gid(ancestor_parent_gid).await.unwrap();
+/// 从 `ancestor_gid` 开始按位置描述列表逐层向下查找成员，任意一层索引越界时返回 None。
+pub fn find_member_by_position(ancestor_gid: u64, indices: &[usize]) -> Option<Member> {
+    let mut cur = get_member_by_gid(ancestor_gid);
+    for idx in indices.iter() {
+        let children = get_ordered_children(cur.gid);
+        cur = children.get(*idx)?.clone();
+    }
+    Some(cur)
+}
 
-    let mut ancestor_index: Option<usize> = None;
-    for (i, s) in siblings.iter().enumerate() {
-        if s.gid == ancestor_gid {
-            ancestor_index = Some(i);
-            break;
+/// 按目录顺序返回 `member_gid` 的子成员，不包含数据成员。
+///
+/// 用于按位置在层次中移动（ParallelPeriod、Cousin、OpeningPeriod 等），隐藏成员保留在结果中，
+/// 以便位置描述与真实的父子关系一致。
+pub fn get_ordered_children(member_gid: u64) -> Vec<Member> {
+    let snapshot = current();
+    snapshot
+        .children_by_parent
+        .get(&member_gid)
+        .map_or(vec![], |gids| {
+            gids.iter()
+                .map(|gid| snapshot.member(*gid).clone())
+                .collect()
+        })
+}
+
+/// 按目录顺序返回 `member_gid` 可见的子成员：不包含数据成员，
+/// 按 HideMemberIf 隐藏的占位成员由其可见的子成员代替（可以连续隐藏多个层级）。
+pub fn get_visible_children(member_gid: u64) -> Vec<Member> {
+    let mut visible: Vec<Member> = Vec::new();
+
+    // 栈中保存待展开的成员，倒序入栈以保持目录顺序
+    let mut pending = get_ordered_children(member_gid);
+    pending.reverse();
+    while let Some(child) = pending.pop() {
+        if is_hidden_member(&child) {
+            let mut grandchildren = get_ordered_children(child.gid);
+            grandchildren.reverse();
+            pending.extend(grandchildren);
+        } else {
+            visible.push(child);
         }
     }
-    let ancestor_index = ancestor_index.expect("Ancestor not found among its parent's children");
+    visible
+}
 
-    // offset: 按调用约定，正数表示向前移动（索引减小），负数表示向后移动（索引增大）——与 MDX ParallelPeriod 的常见语义保持一致
-    let target_idx_signed = ancestor_index as isize - offset as isize;
-    if target_idx_signed < 0 || target_idx_signed as usize >= siblings.len() {
-        panic!(
-            "Shifted ancestor index out of range: base_index={} offset={} result_index={}",
-            ancestor_index, offset, target_idx_signed
-        );
+/// 返回非叶子成员的数据成员；成员本身是叶子时返回成员本身，没有数据成员时返回 None。
+pub fn get_data_member(member_gid: u64) -> Option<Member> {
+    let snapshot = current();
+    let member = snapshot.member(member_gid);
+    if member.leaf || member.data_member {
+        return Some(member.clone());
     }
-    let target_ancestor = siblings[target_idx_signed as usize].clone();
 
-    // 从 target_ancestor 向下按 indices_rev 路径查找最终成员
-    let mut cur = target_ancestor;
-    for idx in indices_rev.iter() {
-        let children = grpc_cli.get_child_members_by_gid(cur.gid).await.unwrap();
-        if *idx >= children.len() {
-            panic!("Child index {} out of range for parent {}", idx, cur.gid);
-        }
-        cur = children[*idx].clone();
+    snapshot
+        .data_member_by_parent
+        .get(&member_gid)
+        .map(|gid| snapshot.member(*gid).clone())
+}
+
+/// 成员是否为按所在层级的 HideMemberIf 规则隐藏的占位成员。
+pub fn is_hidden_member(member: &Member) -> bool {
+    if member.is_null() || member.data_member {
+        return false;
     }
+    let snapshot = current();
+    let hide_member_if = snapshot.level(member.level_gid).hide_member_if;
+    if hide_member_if == HideMemberIf::Never || member.parent_gid == 0 {
+        return false;
+    }
+
+    let parent = snapshot.members.get(&member.parent_gid);
+    let siblings = snapshot
+        .children_by_parent
+        .get(&member.parent_gid)
+        .map_or(0, |children| children.len());
+    hide_member_if.hides(member, parent, siblings)
+}
+
+/// 单元测试使用的元数据。
+#[cfg(test)]
+pub mod test_fixture {
+    use super::MetaSnapshot;
+    use crate::calendar::{DateGranularity, OlapDate};
+    use crate::mdd::{Cube, DimensionRole, HideMemberIf, Hierarchy, Level, Member};
+    use std::collections::HashMap;
 
-    cur
-}
\ No newline at end of file
+    pub const CUBE_GID: u64 = 500000000000001;
+    pub const DIM_ROLE_GID: u64 = 600000000000001;
+    pub const YEAR_LEVEL_GID: u64 = 400000000000001;
+    pub const MONTH_LEVEL_GID: u64 = 400000000000002;
+    pub const DAY_LEVEL_GID: u64 = 400000000000003;
+    pub const YEAR_2024_GID: u64 = 300000000000001;
+    pub const JAN_GID: u64 = 300000000000002;
+    pub const FEB_GID: u64 = 300000000000003;
+
+    const DIMENSION_GID: u64 = 100000000000001;
+    const HIERARCHY_GID: u64 = 200000000000001;
+
+    /// 2024 年 1 月 1 日至 3 日、2 月 1 日至 2 日的日期维度：[2024] > [Jan]、[Feb] > [1]、[2]...
+    /// 日成员的 gid 为 300000000000000 + 月份 * 10 + 日。
+    pub fn calendar_snapshot() -> MetaSnapshot {
+        let level = |gid, name: &str, level, granularity| Level {
+            gid,
+            name: String::from(name),
+            level,
+            dimension_gid: DIMENSION_GID,
+            hierarchy_gid: HIERARCHY_GID,
+            opening_period_gid: 0,
+            closing_period_gid: 0,
+            date_granularity: Some(granularity),
+            hide_member_if: HideMemberIf::Never,
+        };
+        let member =
+            |gid, name: String, level_gid, level, parent_gid, full_path, date: &str| Member {
+                gid,
+                name,
+                level_gid,
+                level,
+                parent_gid,
+                measure_index: 0,
+                leaf: level_gid == DAY_LEVEL_GID,
+                full_path,
+                start_date: OlapDate::parse(date),
+                data_member: false,
+                properties: HashMap::new(),
+            };
+
+        let mut members = vec![member(
+            YEAR_2024_GID,
+            String::from("2024"),
+            YEAR_LEVEL_GID,
+            1,
+            0,
+            vec![YEAR_2024_GID],
+            "2024-01-01",
+        )];
+        for (month_gid, month, name, days) in [(JAN_GID, 1, "Jan", 3), (FEB_GID, 2, "Feb", 2)] {
+            members.push(member(
+                month_gid,
+                String::from(name),
+                MONTH_LEVEL_GID,
+                2,
+                YEAR_2024_GID,
+                vec![YEAR_2024_GID, month_gid],
+                &format!("2024-{:02}-01", month),
+            ));
+            for day in 1..=days {
+                let day_gid = 300000000000000 + month * 10 + day;
+                members.push(member(
+                    day_gid,
+                    day.to_string(),
+                    DAY_LEVEL_GID,
+                    3,
+                    month_gid,
+                    vec![YEAR_2024_GID, month_gid, day_gid],
+                    &format!("2024-{:02}-{:02}", month, day),
+                ));
+            }
+        }
+
+        MetaSnapshot::build(
+            vec![
+                level(YEAR_LEVEL_GID, "Year", 1, DateGranularity::Year),
+                level(MONTH_LEVEL_GID, "Month", 2, DateGranularity::Month),
+                level(DAY_LEVEL_GID, "Day", 3, DateGranularity::Day),
+            ],
+            members,
+            vec![Cube {
+                gid: CUBE_GID,
+                name: String::from("c"),
+            }],
+            vec![Hierarchy {
+                gid: HIERARCHY_GID,
+                name: String::from("Calendar"),
+                dimension_gid: DIMENSION_GID,
+                default_member_gid: YEAR_2024_GID,
+                parent_child: false,
+            }],
+            vec![],
+            vec![DimensionRole {
+                gid: DIM_ROLE_GID,
+                name: String::from("Date"),
+                cube_gid: CUBE_GID,
+                dimension_gid: DIMENSION_GID,
+                default_hierarchy_gid: HIERARCHY_GID,
+                measure_flag: false,
+            }],
+        )
+    }
+}
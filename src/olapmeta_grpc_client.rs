@@ -1,7 +1,8 @@
 // src/olapmeta_grpc_client.rs
 
 use crate::cfg;
-use tokio::time::{sleep, Duration};
+use once_cell::sync::OnceCell;
+use tokio::time::Duration;
 
 use olapmeta::olap_meta_service_client::OlapMetaServiceClient;
 use olapmeta::EmptyParameterRequest;
@@ -10,14 +11,19 @@ use olapmeta::GetDefaultDimensionMemberRequest;
 use olapmeta::GetDimensionRoleByGidRequest;
 use olapmeta::GetDimensionRolesByCubeGidRequest;
 use olapmeta::GetUniversalOlapEntityByGidRequest;
+use olapmeta::MetadataChangeEvent;
 // use olapmeta::GrpcMember;
 use olapmeta::LocateOlapEntityRequest;
 use olapmeta::UniversalOlapEntity;
 use olapmeta::{CubeGidRequest, CubeMetaResponse, CubeNameRequest};
+use olapcore::MetadataSnapshotFile;
+use olapmeta::GrpcDimensionRole;
 use olapmeta::{GrpcUserOlapModelAccess, LoadUserOlapModelAccessesRequest};
 use std::fmt;
-use tonic::{transport::Channel, Request};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Streaming};
 
+use crate::calendar::{DateGranularity, OlapDate};
 use crate::mdd;
 use crate::mdd::MultiDimensionalEntity;
 
@@ -27,11 +33,32 @@ pub mod olapmeta {
     tonic::include_proto!("olapmeta");
 }
 
+// 只由 olap-core 使用的本地元数据快照文件格式
+pub mod olapcore {
+    tonic::include_proto!("olapcore");
+}
+
+// 进程内共享的元数据服务连接，tonic 的 Channel 支持多路复用，断开后会自动重连
+static META_CHANNEL: OnceCell<Channel> = OnceCell::new();
+
+#[derive(Clone)]
 pub struct GrpcClient {
     client: OlapMetaServiceClient<Channel>,
 }
 
-fn grpc_to_olap_member(grpc_olap_obj: UniversalOlapEntity) -> mdd::Member {
+/// 连接在第一次请求时才建立，元数据服务不可用时请求返回错误，不会阻塞调用方。
+/// 从元数据快照文件启动时，元数据服务不可用不影响查询。
+fn lazy_channel(address: &str) -> Channel {
+    Endpoint::from_shared(address.to_string())
+        .unwrap_or_else(|e| panic!("[grpc-001] Invalid meta_grpc_url {}: {}", address, e))
+        .connect_timeout(Duration::from_secs(3))
+        .tcp_keepalive(Some(Duration::from_secs(60)))
+        .http2_keep_alive_interval(Duration::from_secs(30))
+        .keep_alive_while_idle(true)
+        .connect_lazy()
+}
+
+pub fn grpc_to_olap_member(grpc_olap_obj: UniversalOlapEntity) -> mdd::Member {
     mdd::Member {
         gid: grpc_olap_obj.gid,
         name: grpc_olap_obj.name,
@@ -43,33 +70,71 @@ fn grpc_to_olap_member(grpc_olap_obj: UniversalOlapEntity) -> mdd::Member {
         measure_index: grpc_olap_obj.measure_index,
         leaf: grpc_olap_obj.leaf,
         full_path: grpc_olap_obj.member_gid_full_path.clone(),
+        start_date: OlapDate::parse(&grpc_olap_obj.start_date),
+        data_member: grpc_olap_obj.data_member,
+        properties: grpc_olap_obj.properties.clone(),
+    }
+}
+
+pub fn grpc_to_olap_level(olap_obj: UniversalOlapEntity) -> mdd::Level {
+    mdd::Level {
+        gid: olap_obj.gid,
+        name: olap_obj.name,
+        level: olap_obj.level,
+        dimension_gid: olap_obj.dimension_gid,
+        hierarchy_gid: olap_obj.hierarchy_gid,
+        opening_period_gid: olap_obj.opening_period_gid,
+        closing_period_gid: olap_obj.closing_period_gid,
+        date_granularity: DateGranularity::parse(&olap_obj.date_granularity),
+        hide_member_if: mdd::HideMemberIf::parse(&olap_obj.hide_member_if),
+    }
+}
+
+pub fn grpc_to_olap_cube(grpc_olap_obj: UniversalOlapEntity) -> mdd::Cube {
+    mdd::Cube {
+        gid: grpc_olap_obj.gid,
+        name: grpc_olap_obj.name,
+    }
+}
+
+pub fn grpc_to_olap_hierarchy(grpc_olap_obj: UniversalOlapEntity) -> mdd::Hierarchy {
+    mdd::Hierarchy {
+        gid: grpc_olap_obj.gid,
+        name: grpc_olap_obj.name,
+        dimension_gid: grpc_olap_obj.dimension_gid,
+        default_member_gid: grpc_olap_obj.default_member_gid,
+        parent_child: grpc_olap_obj.parent_child,
+    }
+}
+
+pub fn grpc_to_olap_dimension_role(grpc_dr: GrpcDimensionRole) -> mdd::DimensionRole {
+    mdd::DimensionRole {
+        gid: grpc_dr.gid,
+        name: grpc_dr.name,
+        cube_gid: grpc_dr.cube_gid,
+        dimension_gid: grpc_dr.dimension_gid,
+        default_hierarchy_gid: grpc_dr.default_hierarchy_gid,
+        measure_flag: grpc_dr.measure_flag == 1,
     }
 }
 
 impl GrpcClient {
+    /// 返回使用共享连接的客户端，克隆开销很小，可以在多个请求中并发使用。
+    /// 第一次调用时建立连接（进程启动时由 main 调用），连接失败时按指数退避重试。
     pub async fn get_cli() -> Self {
-        let config = cfg::get_cfg();
-        loop {
-            let res = GrpcClient::new(config.meta_grpc_url.clone()).await;
-            match res {
-                Ok(client) => {
-                    return client;
-                }
-                Err(e) => {
-                    println!(
-                        "Failed to connect to gRPC server: {}. Retrying in 3 seconds...",
-                        e
-                    );
-                }
-            }
-            sleep(Duration::from_secs(3)).await;
+        let channel = META_CHANNEL
+            .get_or_init(|| lazy_channel(&cfg::get_cfg().meta_grpc_url))
+            .clone();
+        GrpcClient {
+            client: OlapMetaServiceClient::new(channel),
         }
     }
 
-    // 创建新的客户端实例
-    pub async fn new(address: String) -> Result<Self, Box<dyn std::error::Error>> {
-        let client = OlapMetaServiceClient::connect(address).await?;
-        Ok(GrpcClient { client })
+    #[cfg(test)]
+    pub fn lazy(address: &'static str) -> Self {
+        GrpcClient {
+            client: OlapMetaServiceClient::new(Endpoint::from_static(address).connect_lazy()),
+        }
     }
 
     // 通过 GID 获取 Cube
@@ -110,8 +175,8 @@ impl GrpcClient {
             .into_iter()
             .map(|grpc_dr| mdd::DimensionRole {
                 gid: grpc_dr.gid,
-                // name: grpc_dr.name,
-                // cube_gid: grpc_dr.cube_gid,
+                name: grpc_dr.name,
+                cube_gid: grpc_dr.cube_gid,
                 dimension_gid: grpc_dr.dimension_gid,
                 default_hierarchy_gid: grpc_dr.default_hierarchy_gid,
                 measure_flag: grpc_dr.measure_flag == 1,
@@ -171,6 +236,8 @@ impl GrpcClient {
 
         let dim_role = mdd::DimensionRole {
             gid: grpc_dim_role.gid,
+            name: grpc_dim_role.name,
+            cube_gid: grpc_dim_role.cube_gid,
             dimension_gid: grpc_dim_role.dimension_gid,
             default_hierarchy_gid: grpc_dim_role.default_hierarchy_gid,
             measure_flag: grpc_dim_role.measure_flag == 1,
@@ -199,6 +266,8 @@ impl GrpcClient {
         // 将 grpc response 转换为 mdd::DimensionRole
         let dim_role = mdd::DimensionRole {
             gid: grpc_dim_role.gid,
+            name: grpc_dim_role.name,
+            cube_gid: grpc_dim_role.cube_gid,
             dimension_gid: grpc_dim_role.dimension_gid,
             default_hierarchy_gid: grpc_dim_role.default_hierarchy_gid,
             measure_flag: grpc_dim_role.measure_flag == 1,
@@ -259,114 +328,72 @@ impl GrpcClient {
         ))
     }
 
-    pub async fn get_all_dimension_roles(
+    /// 批量拉取全部 level、member、cube、hierarchy、计算成员和维度角色，保持元数据服务返回的原始格式，
+    /// 可以直接编码保存到本地的元数据快照文件。
+    pub async fn get_all_metadata(
         &mut self,
-    ) -> Result<Vec<mdd::DimensionRole>, Box<dyn std::error::Error>> {
-        let response = self
+    ) -> Result<MetadataSnapshotFile, Box<dyn std::error::Error>> {
+        let levels = self
             .client
-            .get_all_dimension_roles(EmptyParameterRequest {})
-            .await?;
-
-        // 将响应数据解析为 DimensionRole 列表
-        let dimension_roles: Vec<mdd::DimensionRole> = response
-            .into_inner()
-            .dimension_roles
-            .into_iter()
-            .map(|grpc_dr| mdd::DimensionRole {
-                gid: grpc_dr.gid,
-                // name: grpc_dr.name,
-                // cube_gid: grpc_dr.cube_gid,
-                dimension_gid: grpc_dr.dimension_gid,
-                default_hierarchy_gid: grpc_dr.default_hierarchy_gid,
-                measure_flag: grpc_dr.measure_flag == 1,
-            })
-            .collect();
-
-        Ok(dimension_roles)
-    }
-
-    pub async fn get_all_levels(&mut self) -> Result<Vec<mdd::Level>, Box<dyn std::error::Error>> {
-        let response = self.client.get_all_levels(EmptyParameterRequest {}).await?;
-
-        let levels: Vec<mdd::Level> = response
+            .get_all_levels(EmptyParameterRequest {})
+            .await?
             .into_inner()
-            .levels
-            .into_iter()
-            .map(|olap_obj| mdd::Level {
-                gid: olap_obj.gid,
-                name: olap_obj.name,
-                level: olap_obj.level,
-                dimension_gid: olap_obj.dimension_gid,
-                hierarchy_gid: olap_obj.hierarchy_gid,
-                opening_period_gid: olap_obj.opening_period_gid,
-                closing_period_gid: olap_obj.closing_period_gid,
-            })
-            .collect();
-
-        Ok(levels)
-    }
-
-    pub async fn get_all_members(
-        &mut self,
-    ) -> Result<Vec<mdd::Member>, Box<dyn std::error::Error>> {
-        let response = self
+            .levels;
+        let members = self
             .client
             .get_all_members(EmptyParameterRequest {})
-            .await?;
-
-        let members: Vec<mdd::Member> = response
+            .await?
             .into_inner()
-            .members
-            .into_iter()
-            .map(|grpc_olap_obj: olapmeta::UniversalOlapEntity| mdd::Member {
-                gid: grpc_olap_obj.gid,
-                name: grpc_olap_obj.name,
-                // dimension_gid: grpc_member.dimension_gid,
-                // hierarchy_gid: grpc_member.hierarchy_gid,
-                level_gid: grpc_olap_obj.level_gid,
-                level: grpc_olap_obj.level,
-                parent_gid: grpc_olap_obj.parent_gid,
-                measure_index: grpc_olap_obj.measure_index,
-                leaf: grpc_olap_obj.leaf,
-                full_path: grpc_olap_obj.member_gid_full_path.clone(),
-            })
-            .collect();
-
-        Ok(members)
-    }
-
-    pub async fn get_all_cubes(&mut self) -> Result<Vec<mdd::Cube>, Box<dyn std::error::Error>> {
-        let response = self.client.get_all_cubes(EmptyParameterRequest {}).await?;
-
-        let cubes: Vec<mdd::Cube> = response
+            .members;
+        let cubes = self
+            .client
+            .get_all_cubes(EmptyParameterRequest {})
+            .await?
             .into_inner()
-            .cubes
-            .into_iter()
-            .map(|grpc_olap_obj: olapmeta::UniversalOlapEntity| mdd::Cube {
-                gid: grpc_olap_obj.gid,
-                name: grpc_olap_obj.name,
-            })
-            .collect();
-
-        Ok(cubes)
+            .cubes;
+        let hierarchies = match self
+            .client
+            .get_all_hierarchies(EmptyParameterRequest {})
+            .await
+        {
+            Ok(response) => response.into_inner().hierarchies,
+            // 旧版本的元数据服务没有 GetAllHierarchies，由 meta_cache 为每个维度生成默认层次
+            Err(status) if status.code() == tonic::Code::Unimplemented => vec![],
+            Err(status) => return Err(status.into()),
+        };
+        let formula_members = self
+            .client
+            .get_all_formula_members(EmptyParameterRequest {})
+            .await?
+            .into_inner()
+            .formula_members;
+        let dimension_roles = self
+            .client
+            .get_all_dimension_roles(EmptyParameterRequest {})
+            .await?
+            .into_inner()
+            .dimension_roles;
+
+        Ok(MetadataSnapshotFile {
+            format_version: 0,
+            levels,
+            members,
+            cubes,
+            hierarchies,
+            formula_members,
+            dimension_roles,
+        })
     }
 
-    pub async fn get_all_formula_members(
+    /// 订阅元数据变化通知，元数据服务不支持时返回 Unimplemented。
+    pub async fn watch_metadata_changes(
         &mut self,
-    ) -> Result<Vec<UniversalOlapEntity>, Box<dyn std::error::Error>> {
+    ) -> Result<Streaming<MetadataChangeEvent>, tonic::Status> {
         let response = self
             .client
-            .get_all_formula_members(EmptyParameterRequest {})
+            .watch_metadata_changes(EmptyParameterRequest {})
             .await?;
-
-        let formula_members: Vec<UniversalOlapEntity> = response
-            .into_inner()
-            .formula_members
-            .into_iter()
-            .map(|grpc_olap_obj: UniversalOlapEntity| grpc_olap_obj)
-            .collect();
-
-        Ok(formula_members)
+        Ok(response.into_inner())
     }
 
     pub async fn load_user_olap_model_accesses(
@@ -2,7 +2,6 @@ pub mod permis_obj;
 
 use crate::permission::permis_obj::UserOlapModelAccess;
 
-use crate::cfg::get_cfg;
 use crate::mdd::GidType;
 use crate::olapmeta_grpc_client::GrpcClient;
 
@@ -10,23 +9,40 @@ use crate::meta_cache;
 
 use crate::exmdx::mdd::TupleVector;
 
-#[derive(Debug, Clone)]
+/// 是否为配置中的管理员用户。
+pub fn is_admin_user(user_name: &str) -> bool {
+    crate::cfg::get_cfg()
+        .admin_users
+        .as_ref()
+        .is_some_and(|users| users.iter().any(|user| user == user_name))
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct UserAccessesCollection {
     user_accoll: Vec<UserOlapModelAccess>,
 }
 
 impl UserAccessesCollection {
-    pub async fn new(user_name: String) -> Self {
-        let mut meta_grpc_cli = GrpcClient::new(get_cfg().meta_grpc_url)
-            .await
-            .expect("Failed to create client");
+    /// 从元数据服务加载用户的权限，元数据服务不可用时返回错误信息。
+    pub async fn new(user_name: String) -> Result<Self, String> {
+        let mut meta_grpc_cli = GrpcClient::get_cli().await;
 
         let user_accoll = meta_grpc_cli
-            .load_user_olap_model_accesses(user_name)
+            .load_user_olap_model_accesses(user_name.clone())
             .await
-            .unwrap();
+            .map_err(|e| {
+                format!(
+                    "[perm-001] Failed to load the model accesses of user {}: {}",
+                    user_name, e
+                )
+            })?;
+
+        Ok(Self { user_accoll })
+    }
 
-        Self { user_accoll }
+    /// 权限相同的用户可以共享整条语句的缓存结果。
+    pub fn fingerprint(&self) -> u64 {
+        crate::result_cache::access_fingerprint(&self.user_accoll)
     }
 
     pub fn check_access_permission(&self, coordinates: &Vec<TupleVector>) -> Vec<bool> {
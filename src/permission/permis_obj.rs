@@ -7,7 +7,7 @@
 //     bool has_access           = 6,
 // }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct UserOlapModelAccess {
     pub id: u64,
     pub user_name: String,
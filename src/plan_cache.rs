@@ -0,0 +1,120 @@
+// 与度量数据无关的查询计划缓存：解析后的 MDX 语句、每个 Cube 的默认 Tuple，
+// 以及 LookupCube 使用的包含该 Cube 全部计算成员定义的语句
+
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::cfg::{get_cfg, DEFAULT_PARSED_STATEMENT_CACHE_SIZE};
+use crate::exmdx::ast::AstMdxStatement;
+use crate::exmdx::mdd::TupleVector;
+use crate::mdd::Cube;
+use crate::mdx_grammar::MdxStatementParser;
+use crate::mdx_lexer::Lexer as MdxLexer;
+use crate::meta_cache;
+use crate::result_cache::{normalize_statement, CacheVersion, LruCache};
+
+// 缓存的 Cube 数量上限，超出时淘汰最久未使用的 Cube
+const MAX_CACHED_CUBES: usize = 1024;
+
+// 语法树只取决于语句文本，不会过期；按 Cube 缓存的内容以元数据快照的版本作为 key 的一部分，也不会过期
+fn never_stale() -> CacheVersion {
+    (0, 0)
+}
+
+/// 规范化的语句文本 -> 语法树，按条目数限制容量。
+static PARSED_STATEMENTS: Lazy<Mutex<LruCache<String, Arc<AstMdxStatement>>>> = Lazy::new(|| {
+    Mutex::new(LruCache::new(
+        get_cfg()
+            .parsed_statement_cache_size
+            .unwrap_or(DEFAULT_PARSED_STATEMENT_CACHE_SIZE),
+        never_stale,
+    ))
+});
+
+/// key 为 (cube gid, 元数据快照版本)
+type CubeCache<V> = LruCache<(u64, u64), V>;
+
+/// (cube gid, 元数据快照版本) -> 由各维度角色默认成员组成的 Tuple。
+/// 内容由查询固定的快照生成，重新加载元数据后仍在执行的查询继续使用旧版本的条目。
+static CUBE_DEFAULT_TUPLES: Lazy<Mutex<CubeCache<TupleVector>>> =
+    Lazy::new(|| Mutex::new(LruCache::new(MAX_CACHED_CUBES, never_stale)));
+
+/// (cube gid, 元数据快照版本) -> LookupCube 使用的语句。
+static LOOKUP_CUBE_STATEMENTS: Lazy<Mutex<CubeCache<Arc<AstMdxStatement>>>> =
+    Lazy::new(|| Mutex::new(LruCache::new(MAX_CACHED_CUBES, never_stale)));
+
+/// 解析 MDX 语句，空白不同的相同语句共用一个语法树。
+pub fn parse_statement(statement: &str) -> Arc<AstMdxStatement> {
+    let key = normalize_statement(statement);
+    if let Some(ast) = PARSED_STATEMENTS.lock().unwrap().get(&key, never_stale()) {
+        return ast;
+    }
+
+    let ast = Arc::new(
+        MdxStatementParser::new()
+            .parse(MdxLexer::new(statement))
+            .unwrap(),
+    );
+    PARSED_STATEMENTS
+        .lock()
+        .unwrap()
+        .insert(key, ast.clone(), never_stale(), 1);
+    ast
+}
+
+pub fn cube_default_tuple(cube_gid: u64) -> Option<TupleVector> {
+    CUBE_DEFAULT_TUPLES
+        .lock()
+        .unwrap()
+        .get(&(cube_gid, meta_cache::snapshot_version()), never_stale())
+}
+
+/// tuple 由当前任务使用的快照生成，按这个快照的版本缓存。
+pub fn put_cube_default_tuple(cube_gid: u64, tuple: TupleVector) {
+    CUBE_DEFAULT_TUPLES.lock().unwrap().insert(
+        (cube_gid, meta_cache::snapshot_version()),
+        tuple,
+        never_stale(),
+        1,
+    );
+}
+
+/// LookupCube 在另一个 Cube 上求值时使用的语句，WITH 部分包含该 Cube 的全部计算成员。
+/// 计算成员的定义来自元数据，无法解析时返回错误信息。
+pub fn lookup_cube_statement(cube: &Cube) -> Result<Arc<AstMdxStatement>, String> {
+    let key = (cube.gid, meta_cache::snapshot_version());
+    if let Some(ast) = LOOKUP_CUBE_STATEMENTS
+        .lock()
+        .unwrap()
+        .get(&key, never_stale())
+    {
+        return Ok(ast);
+    }
+
+    let mdx_with_str = meta_cache::mdx_formula_members_fragment(cube);
+    // 没有计算成员时省略 WITH 部分
+    let with_clause = if mdx_with_str.is_empty() {
+        String::new()
+    } else {
+        format!("with\n{}\n", mdx_with_str)
+    };
+    let tunnel_mdx = format!(
+        "{}Select {{ ( &0 ) }} on rows\nfrom &{}",
+        with_clause, cube.gid
+    );
+    let ast = MdxStatementParser::new()
+        .parse(MdxLexer::new(&tunnel_mdx))
+        .map(Arc::new)
+        .map_err(|e| {
+            format!(
+                "[lkc-001] Calculated members of cube {} can not be parsed: {:?}",
+                cube.gid, e
+            )
+        })?;
+    LOOKUP_CUBE_STATEMENTS
+        .lock()
+        .unwrap()
+        .insert(key, ast.clone(), never_stale(), 1);
+    Ok(ast)
+}
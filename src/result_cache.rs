@@ -0,0 +1,293 @@
+// 跨查询的结果缓存：聚合服务返回的单元格值，以及整条 MDX 语句的查询结果
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use logos::Logos;
+use once_cell::sync::Lazy;
+
+use crate::agg_service_client::CellKey;
+use crate::calendar::OlapDate;
+use crate::cfg::get_cfg;
+use crate::euclidolap::{GrpcOlapAxis, GrpcOlapVector};
+use crate::mdx_tokens::Token;
+
+// 导入度量数据后递增，之前缓存的单元格值和查询结果全部失效
+static DATA_VERSION: AtomicU64 = AtomicU64::new(0);
+// 重新加载元数据后递增
+static META_VERSION: AtomicU64 = AtomicU64::new(0);
+
+/// 缓存条目写入时的 (数据版本, 元数据版本)，与当前版本不一致的条目视为过期。
+pub type CacheVersion = (u64, u64);
+
+pub fn current_version() -> CacheVersion {
+    (
+        DATA_VERSION.load(Ordering::SeqCst),
+        META_VERSION.load(Ordering::SeqCst),
+    )
+}
+
+pub fn bump_data_version() {
+    DATA_VERSION.fetch_add(1, Ordering::SeqCst);
+    CELL_CACHE.lock().unwrap().clear();
+    STATEMENT_CACHE.lock().unwrap().clear();
+}
+
+pub fn bump_meta_version() {
+    META_VERSION.fetch_add(1, Ordering::SeqCst);
+    CELL_CACHE.lock().unwrap().clear();
+    STATEMENT_CACHE.lock().unwrap().clear();
+}
+
+/// 按估算的字节数限制容量的 LRU 缓存，容量为 0 时不缓存任何内容。
+/// 按条目数限制容量时，每个条目记为 1。
+pub struct LruCache<K, V> {
+    max_bytes: usize,
+    // 取得当前版本的函数，写入时版本已经变化的值不缓存
+    version_fn: fn() -> CacheVersion,
+    used_bytes: usize,
+    tick: u64,
+    // key -> (值, 写入时的版本, 估算字节数, 最近使用时刻)
+    entries: HashMap<K, (V, CacheVersion, usize, u64)>,
+    // 最近使用时刻 -> key，最小的时刻最先淘汰
+    recency: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+    pub fn new(max_bytes: usize, version_fn: fn() -> CacheVersion) -> Self {
+        Self {
+            max_bytes,
+            version_fn,
+            used_bytes: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_bytes > 0
+    }
+
+    pub fn get(&mut self, key: &K, version: CacheVersion) -> Option<V> {
+        let (_, entry_version, _, last_used) = self.entries.get(key)?;
+        if *entry_version != version {
+            self.remove(key);
+            return None;
+        }
+        let last_used = *last_used;
+        self.tick += 1;
+        let tick = self.tick;
+        self.recency.remove(&last_used);
+        self.recency.insert(tick, key.clone());
+        let entry = self.entries.get_mut(key).unwrap();
+        entry.3 = tick;
+        Some(entry.0.clone())
+    }
+
+    /// 写入缓存。version 是开始计算这个值之前取得的版本，
+    /// 计算期间数据或元数据发生变化时不写入，避免缓存过期的结果。
+    pub fn insert(&mut self, key: K, value: V, version: CacheVersion, bytes: usize) {
+        if bytes > self.max_bytes || version != (self.version_fn)() {
+            return;
+        }
+        self.remove(&key);
+        while self.used_bytes + bytes > self.max_bytes {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some((_, _, old_bytes, _)) = self.entries.remove(&oldest) {
+                self.used_bytes -= old_bytes;
+            }
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, version, bytes, self.tick));
+        self.used_bytes += bytes;
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        if let Some((_, _, bytes, last_used)) = self.entries.remove(key) {
+            self.recency.remove(&last_used);
+            self.used_bytes -= bytes;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.used_bytes = 0;
+    }
+}
+
+/// (cube gid, 单元格坐标) -> (值, 是否为 Null)
+pub type CellCache = LruCache<(u64, CellKey), (f64, bool)>;
+
+/// 只缓存聚合服务返回的原始值，与用户权限无关。
+pub static CELL_CACHE: Lazy<Mutex<CellCache>> = Lazy::new(|| {
+    Mutex::new(LruCache::new(
+        get_cfg().cell_cache_max_bytes.unwrap_or(0),
+        current_version,
+    ))
+});
+
+/// (规范化的语句, 用户权限指纹, 查询日期) -> 整条语句的查询结果。
+/// Today() 等与日期有关的查询结果每天都会变化，日期不同的查询不共享结果。
+pub static STATEMENT_CACHE: Lazy<Mutex<LruCache<StatementKey, StatementResult>>> =
+    Lazy::new(|| {
+        Mutex::new(LruCache::new(
+            get_cfg().statement_cache_max_bytes.unwrap_or(0),
+            current_version,
+        ))
+    });
+
+pub type StatementKey = (String, u64, OlapDate);
+
+pub type StatementResult = (u64, Vec<GrpcOlapVector>, Vec<GrpcOlapAxis>);
+
+pub fn cell_entry_bytes(key: &(u64, CellKey)) -> usize {
+    // 条目本身、两份 key（entries 和 recency）以及成员 gid 数组
+    std::mem::size_of::<((u64, CellKey), (f64, bool), CacheVersion, usize, u64)>() * 2
+        + key.1 .0.len() * std::mem::size_of::<u64>() * 2
+}
+
+pub fn statement_entry_bytes(statement: &str, result: &StatementResult) -> usize {
+    let (_, vectors, axes) = result;
+    let vectors_bytes: usize = vectors
+        .iter()
+        .map(|v| std::mem::size_of::<GrpcOlapVector>() + v.str.len() + v.formatted_value.len())
+        .sum();
+    let axes_bytes: usize = axes
+        .iter()
+        .flat_map(|axis| axis.tuples.iter())
+        .flat_map(|tuple| tuple.members.iter())
+        .map(|member| {
+            member.unique_name.len()
+                + member.name.len()
+                + member
+                    .properties
+                    .iter()
+                    .map(|(k, v)| k.len() + v.len())
+                    .sum::<usize>()
+                + 64
+        })
+        .sum();
+    statement.len() * 2 + vectors_bytes + axes_bytes
+}
+
+/// 规范化 MDX 语句作为缓存 key：按词法单元重新拼接，单元之间用一个空格分隔，
+/// 去掉空白和注释，关键字和运算符统一为小写。
+/// 标识符、方括号中的名称和字符串保持原样，它们的大小写可能出现在查询结果中。
+///
+/// key 相同的语句词法单元相同，一定等价；只有名称大小写不同的等价语句各自缓存一份结果。
+pub fn normalize_statement(statement: &str) -> String {
+    let mut lexer = Token::lexer(statement);
+    let mut words: Vec<String> = Vec::new();
+    while let Some(token) = lexer.next() {
+        let slice = lexer.slice();
+        match token {
+            Ok(Token::Identifier(_))
+            | Ok(Token::BracketedString(_))
+            | Ok(Token::QuotedString(_))
+            | Err(_) => words.push(String::from(slice)),
+            Ok(_) => words.push(slice.to_lowercase()),
+        }
+    }
+    words.join(" ")
+}
+
+/// 用户权限的指纹，权限不同的用户看到的单元格不同。
+pub fn access_fingerprint<T: Hash>(accesses: &[T]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    accesses.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_statement, CacheVersion, LruCache};
+
+    fn fixed_version() -> CacheVersion {
+        (1, 1)
+    }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut cache: LruCache<&str, u32> = LruCache::new(3, fixed_version);
+        cache.insert("a", 1, fixed_version(), 1);
+        cache.insert("b", 2, fixed_version(), 1);
+        cache.insert("c", 3, fixed_version(), 1);
+        assert_eq!(cache.get(&"a", fixed_version()), Some(1));
+
+        cache.insert("d", 4, fixed_version(), 1);
+        assert_eq!(cache.get(&"b", fixed_version()), None);
+        assert_eq!(cache.get(&"a", fixed_version()), Some(1));
+        assert_eq!(cache.get(&"c", fixed_version()), Some(3));
+        assert_eq!(cache.get(&"d", fixed_version()), Some(4));
+
+        // 超过容量的值不缓存，也不淘汰已有的条目
+        cache.insert("e", 5, fixed_version(), 4);
+        assert_eq!(cache.get(&"e", fixed_version()), None);
+        assert_eq!(cache.get(&"a", fixed_version()), Some(1));
+    }
+
+    #[test]
+    fn lru_rejects_stale_versions() {
+        let mut cache: LruCache<&str, u32> = LruCache::new(3, fixed_version);
+        // 计算期间版本已经变化
+        cache.insert("a", 1, (0, 1), 1);
+        assert_eq!(cache.get(&"a", fixed_version()), None);
+
+        cache.insert("b", 2, fixed_version(), 1);
+        assert_eq!(cache.get(&"b", (2, 1)), None);
+        // 版本不一致的条目读取时被移除
+        assert_eq!(cache.get(&"b", fixed_version()), None);
+    }
+
+    #[test]
+    fn disabled_lru_caches_nothing() {
+        let mut cache: LruCache<&str, u32> = LruCache::new(0, fixed_version);
+        assert!(!cache.is_enabled());
+        cache.insert("b", 2, fixed_version(), 1);
+        assert_eq!(cache.get(&"b", fixed_version()), None);
+    }
+
+    #[test]
+    fn normalize_collapses_whitespace_outside_literals() {
+        assert_eq!(
+            normalize_statement("  select\n\t{[a   b]}  on 0\nfrom   [c] "),
+            "select { [a   b] } on 0 from [c]"
+        );
+        assert_eq!(
+            normalize_statement(r#"with member [x] as 1, format_string = "0  .0" select"#),
+            r#"with member [x] as 1 , format_string = "0  .0" select"#
+        );
+    }
+
+    #[test]
+    fn normalize_folds_keyword_case_and_drops_comments() {
+        assert_eq!(
+            normalize_statement("SELECT {Today()} ON 0 -- 注释\nFROM [C]"),
+            normalize_statement("select {today()} on 0 from [C]")
+        );
+        // 名称和字符串的大小写保持原样
+        assert_ne!(
+            normalize_statement("select [A] on 0 from [c]"),
+            normalize_statement("select [a] on 0 from [c]")
+        );
+        assert_ne!(
+            normalize_statement(r#"select {"A"} on 0 from [c]"#),
+            normalize_statement(r#"select {"a"} on 0 from [c]"#)
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_escaped_closers() {
+        assert_eq!(normalize_statement("[a]]  b]   x"), "[a]]  b] x");
+        assert_eq!(normalize_statement("[a]]]   x"), "[a]]] x");
+        assert_eq!(normalize_statement(r#""a""  b"   x"#), r#""a""  b" x"#);
+    }
+}